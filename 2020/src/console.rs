@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::str::FromStr;
+
+use strum_macros::EnumString;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Instruction {
+    pub opcode: OpCode,
+    pub value: i32,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum OpCode {
+    Nop,
+    Acc,
+    Jmp,
+}
+
+impl FromStr for Instruction {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(' ').collect();
+        if parts.len() != 2 {
+            return Err(format!("invalid instruction '{}'", s).into());
+        }
+
+        let opcode = OpCode::from_str(parts[0])?;
+        let value: i32 = parts[1].parse()?;
+
+        Ok(Instruction { opcode, value })
+    }
+}
+
+pub fn parse(input: &str) -> Result<Vec<Instruction>, Box<dyn Error>> {
+    input.lines().map(|l| Instruction::from_str(l.trim())).collect()
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CpuState {
+    pub instruction_pointer: i32,
+    pub accumulator: i32,
+}
+
+impl CpuState {
+    fn step(&mut self, instr: &Instruction) {
+        match instr.opcode {
+            OpCode::Nop => self.instruction_pointer += 1,
+            OpCode::Acc => {
+                self.accumulator += instr.value;
+                self.instruction_pointer += 1
+            }
+            OpCode::Jmp => self.instruction_pointer += instr.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Jumped to the instruction directly behind the program
+    Terminated,
+    /// The instructions which are executed over and over, starting with the first repeated one
+    Loop { cycle: Vec<usize> },
+    /// Jumped anywhere else outside of the program
+    OutOfBounds { instruction_pointer: i32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    pub state: CpuState,
+    /// Addresses of all executed instructions in order
+    pub trace: Vec<usize>,
+    pub outcome: Outcome,
+}
+
+/// Run until the program terminates, leaves the program or would execute an instruction a second time
+pub fn run(code: &[Instruction]) -> Execution {
+    let mut state = CpuState::default();
+    let mut trace = vec![];
+    let mut executed_at = vec![None; code.len()];
+
+    let outcome = loop {
+        let ip = state.instruction_pointer;
+        if ip == code.len() as i32 {
+            break Outcome::Terminated;
+        }
+        if ip < 0 || ip > code.len() as i32 {
+            break Outcome::OutOfBounds { instruction_pointer: ip };
+        }
+
+        let ip = ip as usize;
+        if let Some(first) = executed_at[ip] {
+            break Outcome::Loop {
+                cycle: trace[first..].to_vec(),
+            };
+        }
+
+        executed_at[ip] = Some(trace.len());
+        trace.push(ip);
+        state.step(&code[ip]);
+    };
+
+    Execution { state, trace, outcome }
+}
+
+/// Address of the instruction following `instr` at `ip`, `None` if it leaves the program
+fn successor(code: &[Instruction], ip: usize, instr: &Instruction) -> Option<usize> {
+    let mut state = CpuState {
+        instruction_pointer: ip as i32,
+        accumulator: 0,
+    };
+    state.step(instr);
+
+    let next = state.instruction_pointer;
+    if next >= 0 && next <= code.len() as i32 {
+        Some(next as usize)
+    } else {
+        None
+    }
+}
+
+/// Find a single instruction which makes the program terminate when replaced by `patch(instr)`.
+///
+/// Every instruction has exactly one successor, so all addresses from which the program terminates
+/// are found once by walking the successor edges backwards from the end of the program.
+/// A patch on the executed path works if it jumps into one of those addresses, the path up to the
+/// patched instruction is unchanged and cannot be reached again from a terminating address.
+pub fn find_patch<F>(code: &[Instruction], patch: F) -> Option<(usize, Instruction)>
+where
+    F: Fn(&Instruction) -> Option<Instruction>,
+{
+    let mut predecessors = vec![vec![]; code.len() + 1];
+    for (ip, instr) in code.iter().enumerate() {
+        if let Some(next) = successor(code, ip, instr) {
+            predecessors[next].push(ip);
+        }
+    }
+
+    let mut terminates = vec![false; code.len() + 1];
+    terminates[code.len()] = true;
+    let mut queue = VecDeque::from(vec![code.len()]);
+    while let Some(ip) = queue.pop_front() {
+        for &prev in &predecessors[ip] {
+            if !terminates[prev] {
+                terminates[prev] = true;
+                queue.push_back(prev);
+            }
+        }
+    }
+
+    run(code).trace.into_iter().find_map(|ip| {
+        let patched = patch(&code[ip])?;
+        let next = successor(code, ip, &patched)?;
+
+        if terminates[next] {
+            Some((ip, patched))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod test_console {
+    use super::*;
+
+    const EXAMPLE: &str = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+
+    #[test]
+    fn detects_loop() {
+        let code = parse(EXAMPLE).unwrap();
+        let execution = run(&code);
+
+        assert_eq!(execution.state.accumulator, 5);
+        assert_eq!(execution.trace, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(
+            execution.outcome,
+            Outcome::Loop {
+                cycle: vec![1, 2, 6, 7, 3, 4]
+            }
+        );
+    }
+
+    #[test]
+    fn finds_patch() {
+        let mut code = parse(EXAMPLE).unwrap();
+
+        let (ip, patched) = find_patch(&code, |instr| match instr.opcode {
+            OpCode::Jmp => Some(Instruction {
+                opcode: OpCode::Nop,
+                ..*instr
+            }),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(ip, 7);
+
+        code[ip] = patched;
+        let execution = run(&code);
+        assert_eq!(execution.outcome, Outcome::Terminated);
+        assert_eq!(execution.state.accumulator, 8);
+    }
+}
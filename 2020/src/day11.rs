@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use aoc_common::automaton::{Automaton, Dense, Neighborhood, Offsets, Pos};
+
 type SeatMap = Vec<Vec<char>>;
 
 const OCCUPIED: char = '#';
@@ -8,173 +12,74 @@ pub fn main() {
     let seat_map = parse(include_str!("../input/2020/day11.txt"));
 
     let mut now = std::time::Instant::now();
-    println!("Part1: {}  [{}]",  run(&seat_map, iteration_part1), humantime::format_duration(now.elapsed()));
+    println!("Part1: {}  [{}]",  run(&seat_map, Offsets::moore(), 4), humantime::format_duration(now.elapsed()));
 
     now = std::time::Instant::now();
-    println!("Part2: {}  [{}]",  run(&seat_map, iteration_part2), humantime::format_duration(now.elapsed()));
+    println!("Part2: {}  [{}]",  run(&seat_map, line_of_sight(&seat_map), 5), humantime::format_duration(now.elapsed()));
 }
 
 pub fn parse(input: &str) -> SeatMap {
-    let mut map = input.trim().lines().map(|line| {
-        let mut cur = line.trim().chars().collect::<Vec<_>>();
-        cur.insert(0, FLOOR);
-        cur.push(FLOOR);
-        cur
-    }).collect::<Vec<Vec<_>>>();
-
-    map.push(vec![FLOOR; map[0].len()]);
-    map.insert(0, vec![FLOOR; map[0].len()]);
-    map
-}
-
-pub fn run(seat_map_orig: &SeatMap, iterate_fn: fn(&mut SeatMap) -> bool) -> u64 {
-    let mut seat_map_modified = seat_map_orig.clone();
-
-    while iterate_fn(&mut seat_map_modified) { }
-    seat_map_modified.iter().map(|row| row.iter().filter(|c| **c == OCCUPIED).count() as u64).sum()
+    input.trim().lines().map(|line| line.trim().chars().collect::<Vec<_>>()).collect()
 }
 
-pub fn for_all_directions<'a>(mut func: Box<dyn FnMut(i32, i32) + 'a>) {
-    for dir in [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)].iter() { // x, y
-        func(dir.1, dir.0); // y, x
-    }
-}
+/// Simulate until nobody moves anymore, an occupied seat is left if at least `tolerance` neighbors are occupied
+pub fn run(seat_map: &SeatMap, neighborhood: impl Neighborhood<2>, tolerance: usize) -> u64 {
+    let rule = |seat: char, neighbors: &[char]| {
+        let occupied = neighbors.iter().filter(|neighbor| **neighbor == OCCUPIED).count();
 
-pub fn print_map(seat_map: &SeatMap) {
-    for row in seat_map.iter() {
-        println!("{}", row.iter().collect::<String>());
-    }
-    println!();
-}
-
-fn are_equal(seat_map1: &SeatMap, seat_map2: &SeatMap) -> bool {
-    for row in 0..seat_map1.len() {
-        for col in 0..seat_map1[0].len() {
-            if seat_map1[row][col] != seat_map2[row][col] {
-                return true;
-            }
+        match seat {
+            EMPTY if occupied == 0 => OCCUPIED,
+            OCCUPIED if occupied >= tolerance => EMPTY,
+            _ => seat,
         }
-    }
-    false
-}
+    };
 
-pub fn iteration_part1(seat_map: &mut SeatMap) -> bool {
-    let orig_seat_map = seat_map.clone();
-    let rows = seat_map.len();
-    let columms = seat_map[0].len();
-
-    // Prepare
-    for row in 1..rows-1 {
-        for col in 1..columms-1 {
-            if seat_map[row][col] == EMPTY {
-                let mut result = true;
-                for_all_directions(Box::new(|dy, dx| {
-                    result &= seat_map[((row as i32) + dy) as usize][((col as i32) + dx) as usize] != OCCUPIED;
-                }));
-
-                if result { seat_map[row][col] = 'P'; }
-            }
-        }
-    }
+    // Everything outside of the map is floor
+    let mut seats = Automaton::new(Dense::from_rows(seat_map.clone()), neighborhood, rule, FLOOR);
+    seats.run_until_stable();
 
-    update_map(seat_map, 'P', OCCUPIED);
-
-    // Step2
-    for row in 1..rows-1 {
-        for col in 1..columms-1 {
-            if seat_map[row][col] == OCCUPIED {
-                let mut cnt = 0;
-                for_all_directions(Box::new(|dy, dx| {
-                    let v = seat_map[((row as i32) + dy) as usize][((col as i32) + dx) as usize];
-                    if v == OCCUPIED || v == 'E' {
-                        cnt += 1
-                    }
-                }));
+    seats.count(OCCUPIED).unwrap() as u64
+}
 
-                if cnt >= 4 { seat_map[row][col] = 'E'; }
-            }
-        }
-    }
+/// The first seat visible in each of the eight directions
+pub fn line_of_sight(seat_map: &SeatMap) -> impl Fn(Pos<2>, &mut Vec<Pos<2>>) {
+    let is_seat = |[x, y]: Pos<2>| {
+        seat_map.get(y as usize).and_then(|row| row.get(x as usize)).map(|cell| *cell != FLOOR)
+    };
 
-    update_map(seat_map, 'E', EMPTY);
-    are_equal(seat_map, &orig_seat_map)
-}
+    let mut visible = HashMap::new();
 
-fn check_direction_occupied(seat_map: &SeatMap, start_x: usize, start_y: usize, dx: i32, dy: i32, check_char1: char, check_char2: char) -> bool {
-    let rows = seat_map.len() as i32;
-    let columms = seat_map[0].len() as i32;
-    let mut x = start_x as i32;
-    let mut y = start_y as i32;
+    for y in 0..seat_map.len() as i32 {
+        for x in 0..seat_map[0].len() as i32 {
+            let mut seats = vec![];
 
-    loop {
-        x = x + dx;
-        y = y + dy;
+            for [dx, dy] in Offsets::<2>::moore().0 {
+                let mut pos = [x + dx, y + dy];
 
-        if x > 0 && x < rows && y > 0 && y < columms {
-            if seat_map[x as usize][y as usize] == FLOOR {
-                continue;
+                // Negative coordinates wrap to huge indices and are outside as well
+                while let Some(seat) = is_seat(pos) {
+                    if seat {
+                        seats.push(pos);
+                        break;
+                    }
+                    pos = [pos[0] + dx, pos[1] + dy];
+                }
             }
 
-            if seat_map[x as usize][y as usize] == check_char1 || seat_map[x as usize][y as usize] == check_char2 {
-                return true;
-            }
-            else {
-                return false;
-            }
-        }
-        else {
-            return false;
+            visible.insert([x, y], seats);
         }
     }
-}
 
-fn update_map(seat_map: &mut SeatMap, from: char, to: char) {
-    for row in 1..seat_map.len()-1 {
-        for col in 1..seat_map[0].len()-1 {
-            if seat_map[row][col] == from {
-                seat_map[row][col] = to;
-            }
+    move |pos, out: &mut Vec<Pos<2>>| {
+        if let Some(seats) = visible.get(&pos) {
+            out.extend_from_slice(seats);
         }
     }
 }
 
-fn iteration_part2(seat_map: &mut SeatMap) -> bool {
-    let orig_seat_map = seat_map.clone();
-    let rows = seat_map.len();
-    let columms = seat_map[0].len();
-
-    // Prepare
-    for row in 1..rows-1 {
-        for col in 1..columms-1 {
-            if seat_map[row][col] == EMPTY {
-                let mut result = true;
-                for_all_directions(Box::new(|dy, dx| {
-                    result &= !check_direction_occupied(seat_map, row, col, dx, dy, OCCUPIED, ' ');
-                }));
-
-                if result { seat_map[row][col] = 'P'; }
-            }
-        }
-    }
-
-    update_map(seat_map, 'P', OCCUPIED);
-
-    // Step2
-    for row in 1..rows-1 {
-        for col in 1..columms-1 {
-            if seat_map[row][col] == OCCUPIED {
-                let mut cnt = 0;
-                for_all_directions(Box::new(|dy, dx| {
-                    if check_direction_occupied(seat_map, row, col, dx, dy, OCCUPIED, 'E') {
-                        cnt += 1
-                    }
-                }));
-
-                if cnt >= 5 { seat_map[row][col] = 'E'; }
-            }
-        }
+pub fn print_map(seat_map: &SeatMap) {
+    for row in seat_map.iter() {
+        println!("{}", row.iter().collect::<String>());
     }
-
-    update_map(seat_map, 'E', EMPTY);
-    are_equal(seat_map, &orig_seat_map)
+    println!();
 }
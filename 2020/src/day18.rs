@@ -1,131 +1,198 @@
+use std::fmt;
+
 fn main() {
-    let data = parse(include_str!("../input/2020/day18.txt"));
+    let data = parse(include_str!("../input/2020/day18.txt")).unwrap();
 
     let now = std::time::Instant::now();
     let part1 = part1(&data);
     println!("Part1: {}  [{}]", part1, humantime::format_duration(now.elapsed()));
     assert_eq!(part1, 16332191652452);
 
-    assert_eq!(calc2(&parse("2 * 3 + (4 * 5)")[0]).0, 46);
-    assert_eq!(calc2(&parse("5 + (8 * 3 + 9 + 3 * 4 * 3)")[0]).0, 1445);
-    assert_eq!(calc2(&parse("1 + 2 * 3 + 4 * 5 + 6")[0]).0, 231);
-    assert_eq!(calc2(&parse("1 + (2 * 3) + (4 * (5 + 6))")[0]).0, 51);
-    assert_eq!(calc2(&parse("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))")[0]).0, 669060);
-    assert_eq!(calc2(&parse("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2")[0]).0, 23340);
+    let example = |line: &str| Expr::parse(&tokenize(line).unwrap(), ADDITION_FIRST).unwrap();
+    assert_eq!(example("2 * 3 + (4 * 5)").eval(), 46);
+    assert_eq!(example("5 + (8 * 3 + 9 + 3 * 4 * 3)").eval(), 1445);
+    assert_eq!(example("1 + 2 * 3 + 4 * 5 + 6").eval(), 231);
+    assert_eq!(example("1 + (2 * 3) + (4 * (5 + 6))").eval(), 51);
+    assert_eq!(example("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))").eval(), 669060);
+    assert_eq!(example("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2").eval(), 23340);
+    assert_eq!(example("1 + 2 * 3 + 4").to_string(), "((1 + 2) * (3 + 4))");
+
+    let right_to_left = [Operator {
+        symbol: '-',
+        precedence: 1,
+        assoc: Assoc::Right,
+    }];
+    assert_eq!(Expr::parse(&tokenize("8 - 3 - 2").unwrap(), &right_to_left).unwrap().eval(), 7);
 
     let now = std::time::Instant::now();
     let part2 = part2(&data);
     println!("Part2: {}  [{}]", part2, humantime::format_duration(now.elapsed()));
 }
 
-fn parse(input: &str) -> Vec<Vec<char>> {
-    input.trim().replace(" ", "").lines().map(|l| l.trim().chars().collect::<Vec<_>>()).collect::<Vec<_>>()
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(u64),
+    Operator(char),
+    Open,
+    Close,
 }
 
-fn calc2(input: &[char]) -> (u64, usize) {
-    let plus_count = input.iter().filter(|v| **v == '+').count();
-    let mut patched_equation = input.to_vec();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assoc {
+    Left,
+    Right,
+}
 
-    // Add braces around all + operations
-    for nb in 0..plus_count {
-        let pos = patched_equation.iter().enumerate().filter(|(_, c)| **c == '+').collect::<Vec<_>>()[nb].0;
+/// Binary operator with its binding power, higher binds stronger
+#[derive(Debug, Clone, Copy)]
+struct Operator {
+    symbol: char,
+    precedence: u8,
+    assoc: Assoc,
+}
 
-        patch(pos, 1, ')', &mut patched_equation);
-        patch(pos, -1, '(', &mut patched_equation);
+const fn left(symbol: char, precedence: u8) -> Operator {
+    Operator {
+        symbol,
+        precedence,
+        assoc: Assoc::Left,
     }
-
-    // Calc using old part1 func
-    calc(&patched_equation)
 }
 
-fn patch(pos: usize, direction: isize, symbol: char, equation: &mut Vec<char>) {
-    let mut idx: isize = pos as isize;
-    let mut level = 0;
-    loop {
-        let cur = *equation.get(idx as usize).unwrap_or(&' ');
-        if cur == '(' {
-            level += 1;
-        } else if cur == ')' {
-            level -= 1;
-        }
+/// Part 1: evaluated left to right
+const LEFT_TO_RIGHT: &[Operator] = &[left('+', 1), left('*', 1)];
 
-        if cur != '+' && cur != '*' && level == 0 {
-            let mut new_pos = idx;
+/// Part 2: addition before multiplication
+const ADDITION_FIRST: &[Operator] = &[left('+', 2), left('*', 1)];
 
-            if direction > 0 {
-                new_pos += direction;
-            }
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(u64),
+    Binary {
+        op: char,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
 
-            if new_pos >= equation.len() as isize {
-                new_pos = equation.len() as isize;
-            } else if new_pos < 0 {
-                new_pos = 0;
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            ' ' => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '0'..='9' => {
+                let mut nb = ch.to_digit(10).unwrap() as u64;
+                while let Some(digit) = chars.peek().and_then(|ch| ch.to_digit(10)) {
+                    nb = nb * 10 + digit as u64;
+                    chars.next();
+                }
+                tokens.push(Token::Number(nb));
             }
-
-            equation.insert(new_pos as usize, symbol);
-            break;
+            '+' | '-' | '*' | '/' => tokens.push(Token::Operator(ch)),
+            _ => return Err(format!("Unexpected character '{}' in '{}'", ch, line)),
         }
-
-        idx += direction;
     }
-}
 
-fn part1(lines: &Vec<Vec<char>>) -> u64 {
-    lines.iter().map(|l| calc(l).0).sum()
+    Ok(tokens)
 }
 
-fn part2(lines: &Vec<Vec<char>>) -> u64 {
-    lines.iter().map(|l| calc2(l).0).sum()
-}
+impl Expr {
+    /// Pratt parser, `operators` defines precedence and associativity of every supported operator
+    fn parse(tokens: &[Token], operators: &[Operator]) -> Result<Expr, String> {
+        let mut pos = 0;
+        let expr = Expr::parse_bp(tokens, &mut pos, operators, 0)?;
 
-fn calc(input: &[char]) -> (u64, usize) {
-    let mut pos = 0;
-
-    let mut v1: Option<u64> = None;
-    let mut op: Option<char> = None;
-    let mut v2: Option<u64> = None;
-
-    loop {
-        let sym = input[pos];
-        let mut cur: Option<u64> = None;
-
-        if sym == '(' {
-            let (sub_result, end) = calc(&input[pos + 1..]);
-            cur = Some(sub_result);
-            pos += end + 1;
-        } else if sym == ')' {
-            return (v1.unwrap(), pos);
-        } else if sym == '+' || sym == '*' {
-            op = Some(sym);
-        } else {
-            cur = Some(sym.to_digit(10).unwrap() as u64);
+        match tokens.get(pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {:?} at token {}", token, pos)),
         }
+    }
 
-        if cur.is_some() {
-            if v1.is_none() {
-                v1 = cur;
-            } else if v2.is_none() {
-                v2 = cur;
+    fn parse_bp(tokens: &[Token], pos: &mut usize, operators: &[Operator], min_bp: u8) -> Result<Expr, String> {
+        let mut lhs = match tokens.get(*pos) {
+            Some(Token::Number(nb)) => Expr::Number(*nb),
+            Some(Token::Open) => {
+                *pos += 1;
+                let inner = Expr::parse_bp(tokens, pos, operators, 0)?;
+                if tokens.get(*pos) != Some(&Token::Close) {
+                    return Err(format!("Missing ')' at token {}", pos));
+                }
+                inner
             }
+            token => return Err(format!("Expected a number or '(' at token {}, got {:?}", pos, token)),
+        };
+        *pos += 1;
+
+        while let Some(Token::Operator(symbol)) = tokens.get(*pos) {
+            let op = operators
+                .iter()
+                .find(|op| op.symbol == *symbol)
+                .ok_or_else(|| format!("Operator '{}' is not configured", symbol))?;
+
+            // Binding powers are doubled so right associative operators can bind slightly weaker on their left
+            let (left_bp, right_bp) = match op.assoc {
+                Assoc::Left => (op.precedence * 2, op.precedence * 2 + 1),
+                Assoc::Right => (op.precedence * 2 + 1, op.precedence * 2),
+            };
+            if left_bp < min_bp {
+                break;
+            }
+
+            *pos += 1;
+            let rhs = Expr::parse_bp(tokens, pos, operators, right_bp)?;
+            lhs = Expr::Binary {
+                op: *symbol,
+                left: Box::new(lhs),
+                right: Box::new(rhs),
+            };
         }
 
-        if let Some(vv1) = v1 {
-            if let Some(vv2) = v2 {
-                match op.unwrap() {
-                    '+' => v1 = Some(vv1 + vv2),
-                    '*' => v1 = Some(vv1 * vv2),
-                    _ => panic!(),
-                }
+        Ok(lhs)
+    }
 
-                v2 = None;
+    fn eval(&self) -> u64 {
+        match self {
+            Expr::Number(nb) => *nb,
+            Expr::Binary { op, left, right } => {
+                let (left, right) = (left.eval(), right.eval());
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => unreachable!(),
+                }
             }
         }
+    }
+}
 
-        pos += 1;
-        if pos == input.len() {
-            break;
+/// Every operation is wrapped in parentheses, so the parsed precedence is visible
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(nb) => write!(f, "{}", nb),
+            Expr::Binary { op, left, right } => write!(f, "({} {} {})", left, op, right),
         }
     }
+}
+
+fn parse(input: &str) -> Result<Vec<Vec<Token>>, String> {
+    input.trim().lines().map(|l| tokenize(l.trim())).collect()
+}
+
+fn evaluate_all(lines: &[Vec<Token>], operators: &[Operator]) -> u64 {
+    lines.iter().map(|l| Expr::parse(l, operators).unwrap().eval()).sum()
+}
+
+fn part1(lines: &[Vec<Token>]) -> u64 {
+    evaluate_all(lines, LEFT_TO_RIGHT)
+}
 
-    (v1.unwrap(), 0)
+fn part2(lines: &[Vec<Token>]) -> u64 {
+    evaluate_all(lines, ADDITION_FIRST)
 }
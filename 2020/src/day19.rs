@@ -1,6 +1,7 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
-use std::collections::HashMap;
 
 fn main() {
     //let data = parse(include_str!("../input/2020/day19.txt")).unwrap();
@@ -15,6 +16,17 @@ fn main() {
     let part2 = part2(&parse(include_str!("../input/2020/day19.txt")).unwrap());
     println!("Part2: {}  [{}]", part2, humantime::format_duration(now.elapsed()));
     assert_eq!(part2, 363);
+
+    // `aoc <message>` dumps the grammar and how the message is parsed by rule 0
+    if let Some(msg) = std::env::args().nth(1) {
+        let grammar = Grammar::new(&data_example.rules);
+        println!("\n{}", grammar);
+
+        match grammar.parse_tree(0, &msg.chars().collect::<Vec<_>>()) {
+            Some(tree) => println!("{}", tree),
+            None => println!("'{}' does not match rule 0", msg),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -35,12 +47,11 @@ impl FromStr for Rule {
     type Err = Box<dyn Error>;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        if input.contains("|") {
-            let parts = input.trim().split("|").collect::<Vec<_>>();
-
+        if let Some((first, rest)) = input.split_once("|") {
+            // More than two alternatives are nested to the right
             Ok(Rule::Or(
-                Box::new(Rule::from_str(parts[0].trim())?),
-                Box::new(Rule::from_str(parts[1].trim())?),
+                Box::new(Rule::from_str(first.trim())?),
+                Box::new(Rule::from_str(rest.trim())?),
             ))
         } else if input.contains("\"") {
             Ok(Rule::Character(input.trim().chars().nth(1).ok_or("invalid \"_\"")?))
@@ -78,83 +89,290 @@ fn parse(input: &str) -> Result<InputData, Box<dyn Error>> {
     Ok(InputData{rules: rules, messages: messages})
 }
 
-fn part1(data: &InputData) -> usize {
-    //dbg!(data);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Symbol {
+    Terminal(char),
+    NonTerminal(u64),
+}
 
-    let results = data.messages.iter().map(|msg|
-        check_message(data, data.rules.get(&0).unwrap(),msg, 0).iter().any(|r| *r == msg.len())
-    ).collect::<Vec<_>>();
+/// The rules as context-free grammar, every rule has a list of alternative productions
+#[derive(Debug)]
+struct Grammar {
+    productions: HashMap<u64, Vec<Vec<Symbol>>>,
+}
 
-    //dbg!(&results);
-    results.iter().filter(|r| **r).count()
-    //check_message(0, data.messages[0], backtrack_stack)
+/// Earley item: production `production` of `rule` started at `origin`, `dot` symbols of it are matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: u64,
+    production: usize,
+    dot: usize,
+    origin: usize,
 }
 
-fn part2(orig_data: &InputData) -> usize {
-    let mut patched_rules = orig_data.rules.clone();
-    patched_rules.insert(8, Rule::from_str("42 | 42 8").unwrap());
-    patched_rules.insert(11, Rule::from_str("42 31 | 42 11 31").unwrap());
+#[derive(Debug, PartialEq)]
+enum ParseTree {
+    Leaf(char),
+    Node { rule: u64, children: Vec<ParseTree> },
+}
 
-    let data = InputData{rules: patched_rules, messages: orig_data.messages.clone()};
-    //let data = InputData{rules: patched_rules, messages: vec![orig_data.messages[2].clone()]};
-    //let data = InputData{rules: patched_rules, messages: vec!["bbbbbbbaaaabbbbaaabbabaaa".chars().collect()]};
+impl Grammar {
+    fn new(rules: &HashMap<u64, Rule>) -> Self {
+        Grammar {
+            productions: rules.iter().map(|(id, rule)| (*id, Grammar::alternatives(rule))).collect(),
+        }
+    }
 
-    let results = data.messages.iter().map(|msg|
-        (msg.len(), check_message(&data, data.rules.get(&0).unwrap(),msg, 0))
-    ).collect::<Vec<_>>();
+    fn alternatives(rule: &Rule) -> Vec<Vec<Symbol>> {
+        match rule {
+            Rule::Character(ch) => vec![vec![Symbol::Terminal(*ch)]],
+            Rule::Ref(id) => vec![vec![Symbol::NonTerminal(*id)]],
+            Rule::Or(left, right) => [Grammar::alternatives(left), Grammar::alternatives(right)].concat(),
+            Rule::Sub(sub_rules) => sub_rules.iter().fold(vec![vec![]], |prefixes, sub_rule| {
+                let alternatives = Grammar::alternatives(sub_rule);
+                prefixes
+                    .iter()
+                    .flat_map(|prefix| alternatives.iter().map(move |alternative| [&prefix[..], &alternative[..]].concat()))
+                    .collect()
+            }),
+        }
+    }
 
-    //dbg!(&results);
+    /// Earley chart, all items which are possible after `pos` characters of the message.
+    ///
+    /// Handles any recursion, including left recursion, as long as no rule matches the empty string.
+    /// Without empty matches every item is created once, only completions can lead to the same item twice.
+    fn chart(&self, start: u64, msg: &[char]) -> Vec<Vec<Item>> {
+        let mut chart: Vec<Vec<Item>> = vec![vec![]; msg.len() + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); msg.len() + 1];
+        // Items of each position by the rule expected after their dot, so completing a rule is a lookup
+        let mut waiting: Vec<HashMap<u64, Vec<Item>>> = vec![HashMap::new(); msg.len() + 1];
 
-    let rr = results.iter().map(|results| results.1.iter().any(|res| *res == results.0)).collect::<Vec<_>>();
+        waiting[0].insert(start, vec![]);
+        for production in 0..self.productions[&start].len() {
+            chart[0].push(Item { rule: start, production, dot: 0, origin: 0 });
+        }
 
-    //dbg!(&rr);
-    //.and_then(|res| Some(format!("{} = {}",res, msg.len()))).unwrap_or("None".into())
+        for pos in 0..=msg.len() {
+            let mut idx = 0;
+            while idx < chart[pos].len() {
+                let item = chart[pos][idx];
+                idx += 1;
 
-    //dbg!(&results);
-    rr.iter().filter(|r| **r).count()
-    //check_message(0, data.messages[0], backtrack_stack)
-    //0
-}
+                match self.productions[&item.rule][item.production].get(item.dot) {
+                    // Predict, every rule only once per position
+                    Some(Symbol::NonTerminal(next)) => {
+                        let predicted = waiting[pos].contains_key(next);
+                        waiting[pos].entry(*next).or_default().push(item);
+                        if !predicted {
+                            for production in 0..self.productions[next].len() {
+                                chart[pos].push(Item { rule: *next, production, dot: 0, origin: pos });
+                            }
+                        }
+                    }
+                    // Scan
+                    Some(Symbol::Terminal(ch)) => {
+                        if msg.get(pos) == Some(ch) {
+                            chart[pos + 1].push(Item { dot: item.dot + 1, ..item });
+                        }
+                    }
+                    // Complete, the origin is before `pos` because no rule matches the empty string
+                    None => {
+                        for parent in waiting[item.origin].get(&item.rule).into_iter().flatten() {
+                            let next = Item { dot: parent.dot + 1, ..*parent };
+                            if seen[pos].insert(next) {
+                                chart[pos].push(next);
+                            }
+                        }
+                    }
+                }
+            }
+        }
 
-fn check_message(data: &InputData, rule_to_check: &Rule, msg: &Vec<char>, pos: usize) -> Vec<usize> {
+        chart
+    }
 
-    //println!("pos: {}, {:?}", pos, rule_to_check);
+    /// Rules and the spans `(rule, start, end)` of the message they match
+    fn completed(&self, chart: &[Vec<Item>]) -> HashSet<(u64, usize, usize)> {
+        chart
+            .iter()
+            .enumerate()
+            .flat_map(|(end, items)| {
+                items
+                    .iter()
+                    .filter(move |item| item.dot == self.productions[&item.rule][item.production].len())
+                    .map(move |item| (item.rule, item.origin, end))
+            })
+            .collect()
+    }
 
-    let result = match rule_to_check {
-        Rule::Sub(rule) => {
-            let mut positions = vec![pos];
+    fn matches(&self, start: u64, msg: &[char]) -> bool {
+        self.chart(start, msg)[msg.len()].iter().any(|item| {
+            item.rule == start && item.origin == 0 && item.dot == self.productions[&start][item.production].len()
+        })
+    }
 
-            for sub_rule in rule {
-                let mut sub_result = vec![];
-                for cur_position in positions {
-                    if cur_position < msg.len() {
-                        sub_result.extend(check_message(data, sub_rule, msg, cur_position));
-                    }
-                }
+    fn parse_tree(&self, start: u64, msg: &[char]) -> Option<ParseTree> {
+        let completed = self.completed(&self.chart(start, msg));
+        self.build_tree(&completed, msg, start, 0, msg.len(), &mut HashSet::new())
+    }
 
-                positions = sub_result;
-            }
+    fn build_tree(
+        &self,
+        completed: &HashSet<(u64, usize, usize)>,
+        msg: &[char],
+        rule: u64,
+        start: usize,
+        end: usize,
+        visiting: &mut HashSet<(u64, usize, usize)>,
+    ) -> Option<ParseTree> {
+        // Unit rules may refer to each other for the same span
+        if !completed.contains(&(rule, start, end)) || !visiting.insert((rule, start, end)) {
+            return None;
+        }
+
+        let tree = self.productions[&rule].iter().find_map(|production| {
+            self.build_children(completed, msg, production, start, end, visiting)
+                .map(|children| ParseTree::Node { rule, children })
+        });
 
-            positions
-        },
-        Rule::Or(or_left, or_right) => {
-            // try both or parts (necessary for loops) and combine them
-            let left = check_message(data, or_left, msg, pos);
-            let right = check_message(data, or_right, msg, pos);
-
-            [&left[..], &right[..]].concat()
-        },
-        Rule::Ref(ref_rule) => check_message(data, data.rules.get(&ref_rule).unwrap(), msg, pos),
-        Rule::Character(ch) => {
-            if msg[pos] == *ch {
-                vec![pos+1]
+        visiting.remove(&(rule, start, end));
+        tree
+    }
+
+    fn build_children(
+        &self,
+        completed: &HashSet<(u64, usize, usize)>,
+        msg: &[char],
+        symbols: &[Symbol],
+        start: usize,
+        end: usize,
+        visiting: &mut HashSet<(u64, usize, usize)>,
+    ) -> Option<Vec<ParseTree>> {
+        let Some((first, rest)) = symbols.split_first() else {
+            return if start == end { Some(vec![]) } else { None };
+        };
+
+        match first {
+            Symbol::Terminal(ch) if msg.get(start) == Some(ch) && start < end => {
+                let mut children = self.build_children(completed, msg, rest, start + 1, end, visiting)?;
+                children.insert(0, ParseTree::Leaf(*ch));
+                Some(children)
             }
-            else {
-                vec![]
+            Symbol::Terminal(_) => None,
+            Symbol::NonTerminal(sub_rule) => (start + 1..=end).find_map(|mid| {
+                let child = self.build_tree(completed, msg, *sub_rule, start, mid, visiting)?;
+                let mut children = self.build_children(completed, msg, rest, mid, end, visiting)?;
+                children.insert(0, child);
+                Some(children)
+            }),
+        }
+    }
+}
+
+/// Same format as the puzzle input
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids = self.productions.keys().collect::<Vec<_>>();
+        ids.sort();
+
+        for id in ids {
+            let alternatives = self.productions[id]
+                .iter()
+                .map(|production| {
+                    production
+                        .iter()
+                        .map(|symbol| match symbol {
+                            Symbol::Terminal(ch) => format!("\"{}\"", ch),
+                            Symbol::NonTerminal(id) => id.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}: {}", id, alternatives.join(" | "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One line per node, children indented below their rule
+impl fmt::Display for ParseTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_node(tree: &ParseTree, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match tree {
+                ParseTree::Leaf(ch) => writeln!(f, "{:indent$}\"{}\"", "", ch, indent = depth * 2),
+                ParseTree::Node { rule, children } => {
+                    writeln!(f, "{:indent$}{}", "", rule, indent = depth * 2)?;
+                    children.iter().try_for_each(|child| write_node(child, depth + 1, f))
+                }
             }
         }
-    };
 
-    //println!("    {:?}  <- {:?}", rule_to_check, &result);
-    result
+        write_node(self, 0, f)
+    }
+}
+
+fn count_matches(data: &InputData) -> usize {
+    let grammar = Grammar::new(&data.rules);
+    data.messages.iter().filter(|msg| grammar.matches(0, msg)).count()
+}
+
+fn part1(data: &InputData) -> usize {
+    count_matches(data)
+}
+
+fn part2(orig_data: &InputData) -> usize {
+    let mut patched_rules = orig_data.rules.clone();
+    patched_rules.insert(8, Rule::from_str("42 | 42 8").unwrap());
+    patched_rules.insert(11, Rule::from_str("42 31 | 42 11 31").unwrap());
+
+    count_matches(&InputData {
+        rules: patched_rules,
+        messages: orig_data.messages.clone(),
+    })
+}
+
+#[cfg(test)]
+mod test_day19 {
+    use super::*;
+
+    const EXAMPLE: &str = "0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"\n\nababbb";
+
+    fn chars(msg: &str) -> Vec<char> {
+        msg.chars().collect()
+    }
+
+    #[test]
+    fn example() {
+        let data = parse(EXAMPLE).unwrap();
+        let grammar = Grammar::new(&data.rules);
+
+        for msg in ["ababbb", "abbbab"] {
+            assert!(grammar.matches(0, &chars(msg)), "{}", msg);
+        }
+        for msg in ["bababa", "aaabbb", "aaaabbb", "abab"] {
+            assert!(!grammar.matches(0, &chars(msg)), "{}", msg);
+        }
+
+        assert_eq!(
+            grammar.to_string(),
+            "0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"\n"
+        );
+    }
+
+    #[test]
+    fn left_recursion_and_tree() {
+        let data = parse("0: 0 1 | 1 | 2 0 2\n1: \"a\"\n2: \"b\"\n\na").unwrap();
+        let grammar = Grammar::new(&data.rules);
+
+        assert!(grammar.matches(0, &chars("aaaa")));
+        assert!(grammar.matches(0, &chars("baab")));
+        assert!(!grammar.matches(0, &chars("baa")));
+
+        let tree = grammar.parse_tree(0, &chars("bab")).unwrap();
+        assert_eq!(tree.to_string(), "0\n  2\n    \"b\"\n  0\n    1\n      \"a\"\n  2\n    \"b\"\n");
+        assert_eq!(grammar.parse_tree(0, &chars("ab")), None);
+    }
 }
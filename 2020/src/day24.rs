@@ -1,9 +1,10 @@
 use std::collections::HashSet;
 use std::str::FromStr;
-use strum::IntoEnumIterator;
-use strum_macros::{EnumIter, EnumString};
+use strum_macros::EnumString;
 
-#[derive(Debug, Clone, Copy, PartialEq, EnumString, EnumIter)]
+use aoc_common::automaton::{Automaton, Offsets, Sparse};
+
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
 enum Direction {
     #[strum(serialize = "nw")]
     NorthWest,
@@ -22,10 +23,11 @@ enum Direction {
 use Direction::*;
 type InputData = Vec<Vec<Direction>>;
 
+/// Axial hex coordinates, see https://www.redblobgames.com/grids/hexagons/
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Pos {
-    x: i32,
-    y: i32,
+    q: i32,
+    r: i32,
 }
 
 fn main() {
@@ -98,65 +100,37 @@ fn part2(directions: &InputData) -> u32 {
         }
     }
 
-    let all_directions = Direction::iter().collect::<Vec<_>>();
-
-    for _ in 0..100 {
-        let mut blacks_copy: HashSet<_> = blacks.clone();
-
-        // black -> white
-        for cur in &blacks {
-            let mut adjacent = 0;
-            for dir in &all_directions {
-                if blacks.contains(&get_in_direction(*cur, *dir)) {
-                    adjacent += 1;
-                }
-            }
-
-            if adjacent == 0 || adjacent > 2 {
-                blacks_copy.remove(&cur);
-            }
-        }
-
-        // white -> black
-        for cur_black_tile in &blacks {
-            for dir in &all_directions {
-                let cur_white_tile = get_in_direction(*cur_black_tile, *dir);
-                if blacks.contains(&cur_white_tile) {
-                    continue;
-                }
-
-                let mut adjacent = 0;
-                for dir2 in &all_directions {
-                    if blacks.contains(&get_in_direction(cur_white_tile, *dir2)) {
-                        adjacent += 1;
-                    }
-                }
-
-                if adjacent == 2 {
-                    blacks_copy.insert(cur_white_tile);
-                }
+    let mut floor = Automaton::new(
+        Sparse::new(blacks.iter().map(|pos| ([pos.q, pos.r], true))),
+        Offsets::hex(),
+        |black, adjacent: &[bool]| {
+            let adjacent = adjacent.iter().filter(|tile| **tile).count();
+            if black {
+                adjacent == 1 || adjacent == 2
+            } else {
+                adjacent == 2
             }
-        }
-
-        blacks = blacks_copy;
-    }
+        },
+        false,
+    );
 
-    blacks.len() as u32
+    floor.run(100);
+    floor.count(true).unwrap() as u32
 }
 
 fn get_in_direction(pos: Pos, dir: Direction) -> Pos {
     match dir {
-        East => Pos {x: pos.x + 1, y: pos.y},
-        West => Pos {x: pos.x - 1, y: pos.y},
-        NorthEast => Pos {x: pos.x + num::abs(pos.y) % 2, y: pos.y + 1},
-        SouthEast => Pos {x: pos.x + num::abs(pos.y) % 2, y: pos.y - 1},
-        NorthWest => Pos {x: pos.x + (num::abs(pos.y) % 2) - 1, y: pos.y + 1},
-        SouthWest => Pos {x: pos.x + (num::abs(pos.y) % 2) - 1, y: pos.y - 1},
+        East => Pos {q: pos.q + 1, r: pos.r},
+        West => Pos {q: pos.q - 1, r: pos.r},
+        NorthEast => Pos {q: pos.q + 1, r: pos.r - 1},
+        NorthWest => Pos {q: pos.q, r: pos.r - 1},
+        SouthEast => Pos {q: pos.q, r: pos.r + 1},
+        SouthWest => Pos {q: pos.q - 1, r: pos.r + 1},
     }
 }
 
 fn get_end_coor(directions: &Vec<Direction>) -> Pos {
-    let mut pos = Pos { x: 0, y: 0 };
+    let mut pos = Pos { q: 0, r: 0 };
 
     for dir in directions {
         pos = get_in_direction(pos, *dir);
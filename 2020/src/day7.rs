@@ -1,9 +1,7 @@
 use regex::{Regex};
 use std::collections::HashSet;
 
-use petgraph::graphmap::DiGraphMap;
-//use petgraph::dot::{Dot, Config};
-use petgraph::Direction;
+use aoc_common::graph::{Graph, NodeId};
 
 pub fn main() {
     let input = include_str!("../input/2020/day7.txt").trim();
@@ -11,8 +9,6 @@ pub fn main() {
     let graph = parse(input);
     drop(input);
 
-    //println!("{:?}", Dot::with_config(&graph, &[])); // Print graph as graphviz
-
     let mut now = std::time::Instant::now();
     println!("Part1: {}  [{}]", part1("shiny gold", &graph), humantime::format_duration(now.elapsed()));
 
@@ -20,8 +16,8 @@ pub fn main() {
     println!("Part2: {}  [{}]", part2("shiny gold", &graph), humantime::format_duration(now.elapsed()));
 }
 
-pub fn parse(input: &str) -> DiGraphMap::<&str, i64> {
-    let mut g = DiGraphMap::new();
+pub fn parse(input: &str) -> Graph::<String, i64> {
+    let mut g = Graph::new();
 
     let name = Regex::new(r"^(?P<in>\w+ \w+) bags contain ").unwrap();
     let re_inside = Regex::new(r",? ?(?P<count>\d+) (?P<name>\w+ \w+) bags?").unwrap();
@@ -37,19 +33,19 @@ pub fn parse(input: &str) -> DiGraphMap::<&str, i64> {
             let contains = i.name("name").unwrap().as_str();
             let count = i.name("count").unwrap().as_str().parse::<i64>().unwrap();
 
-            g.add_edge(name, contains, count);
+            g.add_weighted_edge(name, contains, count);
         }
     }
 
     g
 }
 
-pub fn part1(dest: &str, graph: &DiGraphMap::<&str, i64>) -> i64 {
+pub fn part1(dest: &str, graph: &Graph::<String, i64>) -> i64 {
     let mut parents = HashSet::new();
-    let mut still_to_check = vec![dest];
+    let mut still_to_check = vec![graph.id(dest).unwrap()];
 
     while still_to_check.len() > 0 {
-        for parent in graph.neighbors_directed(still_to_check.remove(0), Direction::Incoming) {
+        for parent in graph.predecessors(still_to_check.remove(0)) {
             if parents.insert(parent) {
                 still_to_check.push(parent);
             }
@@ -59,11 +55,15 @@ pub fn part1(dest: &str, graph: &DiGraphMap::<&str, i64>) -> i64 {
     parents.len() as i64
 }
 
-pub fn part2(start: &str, graph: &DiGraphMap::<&str, i64>) -> i64 {
+pub fn part2(start: &str, graph: &Graph::<String, i64>) -> i64 {
+    count_bags(graph.id(start).unwrap(), graph)
+}
+
+fn count_bags(start: NodeId, graph: &Graph::<String, i64>) -> i64 {
     let mut count = 0;
 
-    for edge in graph.edges(start) {
-        count += *edge.2 + *edge.2 * part2(edge.1, graph);
+    for (inside, amount) in graph.edges(start) {
+        count += *amount + *amount * count_bags(inside, graph);
     }
 
     count
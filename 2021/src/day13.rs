@@ -1,6 +1,7 @@
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::str::FromStr;
+use aoc_common::ocr;
 use ndarray::Array2;
 
 const INPUT: &str = include_str!("../input/2021/day13.txt");
@@ -138,13 +139,24 @@ impl Paper {
     fn len(&self) -> usize {
         self.paper.len()
     }
+
+    fn pixels(&self) -> Vec<Vec<bool>> {
+        let width = self.paper.iter().map(|p| p.x as usize + 1).max().unwrap_or(0);
+        let height = self.paper.iter().map(|p| p.y as usize + 1).max().unwrap_or(0);
+
+        let mut pixels = vec![vec![false; width]; height];
+        for point in &self.paper {
+            pixels[point.y as usize][point.x as usize] = true;
+        }
+        pixels
+    }
 }
 
 pub fn main() {
     let paper = INPUT.parse::<Paper>().unwrap();
 
     println!("Part1: {}", part1(&paper));
-    println!("Part2:\n{}", part2(&paper));
+    println!("Part2: {}", part2(&paper));
 }
 
 fn part1(paper: &Paper) -> usize {
@@ -153,10 +165,10 @@ fn part1(paper: &Paper) -> usize {
     paper.len()
 }
 
-fn part2(paper: &Paper) -> impl Display {
+fn part2(paper: &Paper) -> String {
     let mut paper = paper.clone();
     paper.execute_all_folds();
-    paper
+    ocr::recognize_or_art(&paper.pixels())
 }
 
 #[cfg(test)]
@@ -178,9 +190,9 @@ mod tests {
 
     #[test]
     fn part2_example() {
-        let expected = "#####\n#   #\n#   #\n#   #\n#####";
-        let result = format!("{}", part2(&EXAMPLE.parse().unwrap()));
-        assert_eq!(expected, result.trim());
+        // A square is no letter, so the art is returned instead
+        let expected = "#####\n#...#\n#...#\n#...#\n#####";
+        assert_eq!(expected, part2(&EXAMPLE.parse().unwrap()).trim());
     }
 
     #[test]
@@ -190,14 +202,6 @@ mod tests {
 
     #[test]
     fn part2_input() {
-        let expected =
-"##  #    ###  #### #  # #### #  # #  #
-#  # #    #  # #    # #  #    # #  #  #
-#  # #    #  # ###  ##   ###  ##   #  #
-#### #    ###  #    # #  #    # #  #  #
-#  # #    # #  #    # #  #    # #  #  #
-#  # #### #  # #### #  # #    #  #  ##";
-        let result = format!("{}", part2(&INPUT.parse().unwrap()));
-        assert_eq!(expected, result.trim());
+        assert_eq!("ALREKFKU", part2(&INPUT.parse().unwrap()));
     }
 }
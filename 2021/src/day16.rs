@@ -1,3 +1,4 @@
+use std::fmt::{self, Display};
 use std::str::FromStr;
 
 use bitvec::prelude::*;
@@ -12,6 +13,16 @@ pub fn main() {
     let data = INPUT.parse::<Packet>().unwrap();
     println!("Parsing [{}]\n", humantime::format_duration(now.elapsed()));
 
+    // `aoc --tree` shows the decoded transmission
+    if std::env::args().any(|arg| arg == "--tree") {
+        println!("{}", data);
+    }
+
+    // `aoc --hex` encodes the transmission again, once for each preferred length type
+    if std::env::args().any(|arg| arg == "--hex") {
+        println!("{}\n{}\n", data.to_hex(), data.to_hex_with(LengthType::TotalLength));
+    }
+
     now = std::time::Instant::now();
     println!("Part1: {} [{}]", part1(&data), humantime::format_duration(now.elapsed()));
 
@@ -19,7 +30,7 @@ pub fn main() {
     println!("Part2: {} [{}]", part2(&data), humantime::format_duration(now.elapsed()));
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Packet {
     Literal {
         version: u8,
@@ -32,7 +43,7 @@ enum Packet {
     },
 }
 
-#[derive(Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
 #[repr(u8)]
 enum PacketType {
     Sum = 0,
@@ -45,6 +56,15 @@ enum PacketType {
     EqualTo,
 }
 
+/// How the subpackets of an operator are delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LengthType {
+    /// Length type ID 0, 15 bit length of all subpackets in bits
+    TotalLength,
+    /// Length type ID 1, 11 bit number of subpackets
+    SubpacketCount,
+}
+
 impl FromStr for Packet {
     type Err = Box<dyn std::error::Error>;
 
@@ -64,7 +84,7 @@ impl FromStr for Packet {
 }
 
 impl Packet {
-    fn from_bits(bits: &BitSlice<bitvec::order::Msb0, u8>) -> Result<(Self, usize), Box<dyn std::error::Error>> {
+    fn from_bits(bits: &BitSlice<u8, Msb0>) -> Result<(Self, usize), Box<dyn std::error::Error>> {
         let version = bits[0..3].load_be::<u8>();
         let packet_type = PacketType::from_u8(bits[3..6].load_be::<u8>()).ok_or("invalid type")?;
 
@@ -94,7 +114,7 @@ impl Packet {
             }
             _ => match bits[6] {
                 false => {
-                    let dst = bits![mut Msb0, u16; 0; 15];
+                    let dst = bits![mut u16, Msb0; 0; 15];
                     dst.clone_from_bitslice(&bits[7..22]);
                     let sub_packet_length = dst.load_be::<u16>();
 
@@ -121,7 +141,7 @@ impl Packet {
                     ))
                 }
                 true => {
-                    let dst = bits![mut Msb0, u16; 0; 11];
+                    let dst = bits![mut u16, Msb0; 0; 11];
                     dst.clone_from_bitslice(&bits[7..18]);
                     let sub_packet_count = dst.load_be::<u16>();
 
@@ -146,6 +166,70 @@ impl Packet {
         }
     }
 
+    /// Hex transmission, subpackets are counted whenever possible as that needs the fewest bits
+    fn to_hex(&self) -> String {
+        self.to_hex_with(LengthType::SubpacketCount)
+    }
+
+    /// Hex transmission, `preferred` is used for all operators unless their subpackets do not fit into it
+    fn to_hex_with(&self, preferred: LengthType) -> String {
+        let mut bits = BitVec::<u8, Msb0>::new();
+        self.to_bits(&mut bits, preferred);
+
+        // The transmission is padded with zeros to full bytes
+        let padding = (8 - bits.len() % 8) % 8;
+        bits.resize(bits.len() + padding, false);
+
+        bits.as_raw_slice().iter().map(|byte| format!("{:02X}", byte)).collect()
+    }
+
+    fn to_bits(&self, bits: &mut BitVec<u8, Msb0>, preferred: LengthType) {
+        let push = |bits: &mut BitVec<u8, Msb0>, value: u64, width: usize| {
+            bits.extend((0..width).rev().map(|bit| value >> bit & 1 == 1));
+        };
+
+        match self {
+            Packet::Literal { version, value } => {
+                push(bits, *version as u64, 3);
+                push(bits, PacketType::Literal as u64, 3);
+
+                let groups = (64 - value.leading_zeros() as usize).div_ceil(4);
+                for group in (0..groups.max(1)).rev() {
+                    push(bits, (group > 0) as u64, 1);
+                    push(bits, value >> (group * 4) & 0xF, 4);
+                }
+            }
+            Packet::Operator {
+                version,
+                packet_type,
+                subpackets,
+            } => {
+                push(bits, *version as u64, 3);
+                push(bits, *packet_type as u64, 3);
+
+                let mut sub_bits = BitVec::<u8, Msb0>::new();
+                for subpacket in subpackets {
+                    subpacket.to_bits(&mut sub_bits, preferred);
+                }
+
+                let fits_count = subpackets.len() < 1 << 11;
+                let fits_length = sub_bits.len() < 1 << 15;
+                match (preferred, fits_count, fits_length) {
+                    (LengthType::TotalLength, _, true) | (LengthType::SubpacketCount, false, true) => {
+                        push(bits, 0, 1);
+                        push(bits, sub_bits.len() as u64, 15);
+                    }
+                    (_, true, _) => {
+                        push(bits, 1, 1);
+                        push(bits, subpackets.len() as u64, 11);
+                    }
+                    (_, false, false) => panic!("Operator has too many subpackets to encode"),
+                }
+                bits.extend_from_bitslice(&sub_bits);
+            }
+        }
+    }
+
     fn get_value(&self) -> usize {
         match self {
             Packet::Literal { value, .. } => *value as usize,
@@ -185,6 +269,27 @@ impl Packet {
     }
 }
 
+/// Indented tree, one packet per line
+impl Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        match self {
+            Packet::Literal { version, value } => writeln!(f, "{:indent$}v{} Literal {}", "", version, value),
+            Packet::Operator {
+                version,
+                packet_type,
+                subpackets,
+            } => {
+                writeln!(f, "{:indent$}v{} {:?}", "", version, packet_type)?;
+                subpackets
+                    .iter()
+                    .try_for_each(|subpacket| write!(f, "{:width$}", subpacket, width = indent + 2))
+            }
+        }
+    }
+}
+
 fn part1(packet: &Packet) -> usize {
     match packet {
         Packet::Literal { version, .. } => *version as usize,
@@ -200,6 +305,8 @@ fn part2(packet: &Packet) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use aoc_common::random::Xorshift;
+
     use super::*;
 
     #[test]
@@ -268,4 +375,63 @@ mod tests {
     fn part2_on_input() {
         assert_eq!(834151779165, part2(&INPUT.parse().unwrap()));
     }
+
+    #[test]
+    fn encode_examples() {
+        assert_eq!("D2FE28", "D2FE28".parse::<Packet>().unwrap().to_hex());
+        assert_eq!("EE00D40C823060", "EE00D40C823060".parse::<Packet>().unwrap().to_hex());
+        assert_eq!(
+            "38006F45291200",
+            "38006F45291200".parse::<Packet>().unwrap().to_hex_with(LengthType::TotalLength)
+        );
+    }
+
+    #[test]
+    fn print_tree() {
+        let packet: Packet = "9C0141080250320F1802104A08".parse().unwrap();
+        let expected = "v4 EqualTo\n  v2 Sum\n    v2 Literal 1\n    v4 Literal 3\n  v6 Product\n    v0 Literal 2\n    v2 Literal 2\n";
+        assert_eq!(expected, packet.to_string());
+    }
+
+    fn random_packet(rng: &mut Xorshift, depth: usize) -> Packet {
+        let version = rng.next(8) as u8;
+
+        if depth == 0 || rng.next(3) == 0 {
+            // Mix small values with ones using all 64 bits
+            let value = match rng.next(3) {
+                0 => rng.next(16),
+                1 => rng.next(u64::MAX),
+                _ => rng.next(1 << 20),
+            };
+            Packet::Literal { version, value }
+        } else {
+            let packet_type = PacketType::from_u64([0, 1, 2, 3, 5, 6, 7][rng.next(7) as usize]).unwrap();
+            let subpackets = (0..=rng.next(4)).map(|_| random_packet(rng, depth - 1)).collect();
+            Packet::Operator {
+                version,
+                packet_type,
+                subpackets,
+            }
+        }
+    }
+
+    #[test]
+    fn decode_encode_roundtrip() {
+        let mut rng = Xorshift::new(0x2021_1216);
+
+        for _ in 0..500 {
+            let packet = random_packet(&mut rng, 4);
+
+            for length_type in [LengthType::TotalLength, LengthType::SubpacketCount] {
+                let hex = packet.to_hex_with(length_type);
+                assert_eq!(packet, hex.parse().unwrap(), "{}\n{}", hex, packet);
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_input() {
+        let packet: Packet = INPUT.parse().unwrap();
+        assert_eq!(packet, packet.to_hex().parse().unwrap());
+    }
 }
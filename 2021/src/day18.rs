@@ -1,8 +1,6 @@
-use std::cell::{Cell, RefCell};
 use std::error::Error;
-use std::fmt::{self, Debug, Formatter};
-use std::rc::Rc;
-use std::rc::Weak;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str::FromStr;
 use itertools::Itertools;
 
 #[derive(Copy, Clone, PartialEq)]
@@ -30,7 +28,7 @@ impl Debug for Symbol {
 impl Debug for Sfn {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for symbol in &self.symbols {
-            write!(f, "{:?} ", symbol);
+            write!(f, "{:?} ", symbol)?;
         }
         Ok(())
     }
@@ -43,6 +41,11 @@ pub fn main() {
     let data = parse_input(INPUT).unwrap();
     println!("Parsing [{}]\n", humantime::format_duration(now.elapsed()));
 
+    // `aoc --trace` adds up the numbers as trees and shows every explode and split
+    if std::env::args().any(|arg| arg == "--trace") {
+        trace(INPUT).unwrap();
+    }
+
     now = std::time::Instant::now();
     println!(
         "Part1: {} [{}]",
@@ -58,6 +61,29 @@ pub fn main() {
     );
 }
 
+fn trace(input: &str) -> Result<(), Box<dyn Error>> {
+    let trees = input.trim().lines().map(SnailTree::from_str).collect::<Result<Vec<_>, _>>()?;
+    let Some((first, rest)) = trees.split_first() else {
+        return Ok(());
+    };
+
+    let mut sum = first.clone();
+    for tree in rest {
+        let mut log = vec![];
+        let next = sum.add(tree, Some(&mut log));
+
+        println!("  {}\n+ {}", sum, tree);
+        for step in log {
+            println!("  {}", step);
+        }
+        println!("= {}\n", next);
+        sum = next;
+    }
+    println!("Magnitude: {}\n", sum.magnitude());
+
+    Ok(())
+}
+
 fn parse_input(input: &str) -> Result<Vec<Sfn>, Box<dyn Error>> {
     input.trim().lines().map(Sfn::from_str).collect()
 }
@@ -98,25 +124,21 @@ impl Sfn {
     fn from_chars(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Sfn, Box<dyn Error>> {
         let mut symbols: Vec<Symbol> = vec![];
 
-        loop {
-            if let Some(token) = chars.next() {
-                if token == ',' {
-                    continue;
-                } else if token == '[' {
-                    symbols.push(Symbol::OpenBracket);
-                } else if token == ']' {
-                    symbols.push(Symbol::ClosingBracket);
-                } else if token.is_digit(10) {
-                    if let Some(next_char) = chars.peek() {
-                        if next_char.is_digit(10) {
-                            symbols.push(Symbol::Number(format!("{}{}", token, chars.next().unwrap()).parse()?));
-                            continue;
-                        }
+        while let Some(token) = chars.next() {
+            if token == ',' {
+                continue;
+            } else if token == '[' {
+                symbols.push(Symbol::OpenBracket);
+            } else if token == ']' {
+                symbols.push(Symbol::ClosingBracket);
+            } else if token.is_ascii_digit() {
+                if let Some(next_char) = chars.peek() {
+                    if next_char.is_ascii_digit() {
+                        symbols.push(Symbol::Number(format!("{}{}", token, chars.next().unwrap()).parse()?));
+                        continue;
                     }
-                    symbols.push(Symbol::Number(token.to_digit(10).unwrap() as u32));
                 }
-            } else {
-                break;
+                symbols.push(Symbol::Number(token.to_digit(10).unwrap()));
             }
         }
         Ok(Sfn { symbols })
@@ -177,7 +199,6 @@ impl Sfn {
             // }
             index += direction;
         }
-        unreachable!();
     }
 
     fn explode(&mut self) -> bool {
@@ -234,6 +255,201 @@ impl Sfn {
     }
 }
 
+/// Element of the `SnailTree` arena, pairs refer to their children by index
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Node {
+    Regular(u32),
+    Pair(usize, usize),
+}
+
+/// Snailfish number as tree, all nodes are stored in one arena
+#[derive(Clone, Debug)]
+struct SnailTree {
+    nodes: Vec<Node>,
+    root: usize,
+}
+
+impl FromStr for SnailTree {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        fn parse_node(
+            chars: &mut std::iter::Peekable<std::str::Chars>,
+            nodes: &mut Vec<Node>,
+        ) -> Result<usize, Box<dyn Error>> {
+            let node = match chars.next() {
+                Some('[') => {
+                    let left = parse_node(chars, nodes)?;
+                    if chars.next() != Some(',') {
+                        return Err("expected ','".into());
+                    }
+                    let right = parse_node(chars, nodes)?;
+                    if chars.next() != Some(']') {
+                        return Err("expected ']'".into());
+                    }
+                    Node::Pair(left, right)
+                }
+                Some(digit) if digit.is_ascii_digit() => {
+                    let mut number = digit.to_digit(10).unwrap();
+                    while let Some(digit) = chars.peek().and_then(|ch| ch.to_digit(10)) {
+                        number = number * 10 + digit;
+                        chars.next();
+                    }
+                    Node::Regular(number)
+                }
+                other => return Err(format!("unexpected {:?}", other).into()),
+            };
+
+            nodes.push(node);
+            Ok(nodes.len() - 1)
+        }
+
+        let mut nodes = vec![];
+        let mut chars = input.trim().chars().peekable();
+        let root = parse_node(&mut chars, &mut nodes)?;
+
+        match chars.next() {
+            None => Ok(SnailTree { nodes, root }),
+            Some(ch) => Err(format!("unexpected '{}' after number", ch).into()),
+        }
+    }
+}
+
+/// Puzzle notation, e.g. `[[1,2],3]`
+impl Display for SnailTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn write_node(tree: &SnailTree, node: usize, f: &mut Formatter<'_>) -> fmt::Result {
+            match tree.nodes[node] {
+                Node::Regular(number) => write!(f, "{}", number),
+                Node::Pair(left, right) => {
+                    write!(f, "[")?;
+                    write_node(tree, left, f)?;
+                    write!(f, ",")?;
+                    write_node(tree, right, f)?;
+                    write!(f, "]")
+                }
+            }
+        }
+
+        write_node(self, self.root, f)
+    }
+}
+
+impl SnailTree {
+    /// Reduced sum, `log` receives the number after every explode and split
+    fn add(&self, other: &SnailTree, log: Option<&mut Vec<String>>) -> SnailTree {
+        let offset = self.nodes.len();
+        let mut nodes = self.nodes.clone();
+        nodes.extend(other.nodes.iter().map(|node| match *node {
+            Node::Pair(left, right) => Node::Pair(left + offset, right + offset),
+            regular => regular,
+        }));
+        nodes.push(Node::Pair(self.root, other.root + offset));
+
+        let mut sum = SnailTree {
+            root: nodes.len() - 1,
+            nodes,
+        };
+        sum.reduce(log);
+        sum
+    }
+
+    fn reduce(&mut self, mut log: Option<&mut Vec<String>>) {
+        loop {
+            let action = if self.explode() {
+                "explode"
+            } else if self.split() {
+                "split"
+            } else {
+                break;
+            };
+
+            if let Some(log) = log.as_mut() {
+                log.push(format!("after {}: {}", action, self));
+            }
+        }
+    }
+
+    /// Regular numbers from left to right
+    fn leaves(&self) -> Vec<usize> {
+        let mut leaves = vec![];
+        let mut stack = vec![self.root];
+
+        while let Some(node) = stack.pop() {
+            match self.nodes[node] {
+                Node::Regular(_) => leaves.push(node),
+                Node::Pair(left, right) => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+
+        leaves
+    }
+
+    /// Leftmost pair nested inside four pairs
+    fn find_exploding(&self, node: usize, depth: usize) -> Option<usize> {
+        match self.nodes[node] {
+            Node::Regular(_) => None,
+            Node::Pair(..) if depth == 4 => Some(node),
+            Node::Pair(left, right) => self
+                .find_exploding(left, depth + 1)
+                .or_else(|| self.find_exploding(right, depth + 1)),
+        }
+    }
+
+    fn explode(&mut self) -> bool {
+        let Some(pair) = self.find_exploding(self.root, 0) else {
+            return false;
+        };
+        let Node::Pair(left, right) = self.nodes[pair] else {
+            unreachable!()
+        };
+        let (Node::Regular(left_value), Node::Regular(right_value)) = (self.nodes[left], self.nodes[right]) else {
+            unreachable!("pairs never nest deeper than five levels")
+        };
+
+        let leaves = self.leaves();
+        let pos = leaves.iter().position(|leaf| *leaf == left).unwrap();
+
+        if let Some(Node::Regular(number)) = pos.checked_sub(1).map(|idx| &mut self.nodes[leaves[idx]]) {
+            *number += left_value;
+        }
+        if let Some(Node::Regular(number)) = leaves.get(pos + 2).map(|idx| &mut self.nodes[*idx]) {
+            *number += right_value;
+        }
+
+        self.nodes[pair] = Node::Regular(0);
+        true
+    }
+
+    fn split(&mut self) -> bool {
+        let Some((leaf, number)) = self.leaves().into_iter().find_map(|leaf| match self.nodes[leaf] {
+            Node::Regular(number) if number > 9 => Some((leaf, number)),
+            _ => None,
+        }) else {
+            return false;
+        };
+
+        self.nodes.push(Node::Regular(number / 2));
+        self.nodes.push(Node::Regular(number - number / 2));
+        self.nodes[leaf] = Node::Pair(self.nodes.len() - 2, self.nodes.len() - 1);
+        true
+    }
+
+    fn magnitude(&self) -> usize {
+        fn node_magnitude(tree: &SnailTree, node: usize) -> usize {
+            match tree.nodes[node] {
+                Node::Regular(number) => number as usize,
+                Node::Pair(left, right) => 3 * node_magnitude(tree, left) + 2 * node_magnitude(tree, right),
+            }
+        }
+
+        node_magnitude(self, self.root)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,34 +484,34 @@ mod tests {
     #[test]
     fn split() {
         let mut check = Sfn::from_str("[13,2]").unwrap();
-        assert_eq!(true, check.split());
+        assert!(check.split());
         assert_eq!(Sfn::from_str("[[6,7],2]").unwrap(), check);
 
         let mut check = Sfn::from_str("[4,17]").unwrap();
-        assert_eq!(true, check.split());
+        assert!(check.split());
         assert_eq!(Sfn::from_str("[4,[8,9]]").unwrap(), check);
 
         let mut check = Sfn::from_str("[4,18]").unwrap();
-        assert_eq!(true, check.split());
+        assert!(check.split());
         assert_eq!(Sfn::from_str("[4,[9,9]]").unwrap(), check);
 
         let mut check = Sfn::from_str("[13,18]").unwrap();
-        assert_eq!(true, check.split());
+        assert!(check.split());
         assert_eq!(Sfn::from_str("[[6,7],18]").unwrap(), check);
 
         let mut check = Sfn::from_str("[3,2]").unwrap();
-        assert_eq!(false, check.split());
+        assert!(!check.split());
         assert_eq!(Sfn::from_str("[3,2]").unwrap(), check);
     }
 
     #[test]
     fn explode() {
         let mut check = Sfn::from_str("[[6,[5,[4,[3,2]]]],1]").unwrap();
-        assert_eq!(true, check.explode());
+        assert!(check.explode());
         assert_eq!(Sfn::from_str("[[6,[5,[7,0]]],3]").unwrap(), check);
 
         let mut check = Sfn::from_str("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]").unwrap();
-        assert_eq!(true, check.explode());
+        assert!(check.explode());
         assert_eq!(Sfn::from_str("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]").unwrap(), check);
     }
 
@@ -324,22 +540,22 @@ mod tests {
 
     #[test]
     fn magnitudes() {
-        let mut check = Sfn::from_str("[[1,2],[[3,4],5]]").unwrap();
+        let check = Sfn::from_str("[[1,2],[[3,4],5]]").unwrap();
         assert_eq!(143, check.magnitude());
 
-        let mut check = Sfn::from_str("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]").unwrap();
+        let check = Sfn::from_str("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]").unwrap();
         assert_eq!(1384, check.magnitude());
 
-        let mut check = Sfn::from_str("[[[[1,1],[2,2]],[3,3]],[4,4]]").unwrap();
+        let check = Sfn::from_str("[[[[1,1],[2,2]],[3,3]],[4,4]]").unwrap();
         assert_eq!(445, check.magnitude());
 
-        let mut check = Sfn::from_str("[[[[3,0],[5,3]],[4,4]],[5,5]]").unwrap();
+        let check = Sfn::from_str("[[[[3,0],[5,3]],[4,4]],[5,5]]").unwrap();
         assert_eq!(791, check.magnitude());
 
-        let mut check = Sfn::from_str("[[[[5,0],[7,4]],[5,5]],[6,6]]").unwrap();
+        let check = Sfn::from_str("[[[[5,0],[7,4]],[5,5]],[6,6]]").unwrap();
         assert_eq!(1137, check.magnitude());
 
-        let mut check = Sfn::from_str("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").unwrap();
+        let check = Sfn::from_str("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").unwrap();
         assert_eq!(3488, check.magnitude());
     }
 
@@ -348,4 +564,59 @@ mod tests {
     fn example_part2() {
         assert_eq!(3993, part2(&parse_input(EXAMPLE).unwrap()));
     }
+
+    #[test]
+    fn tree_display_roundtrip() {
+        for line in INPUT.trim().lines().chain(EXAMPLE.trim().lines()) {
+            assert_eq!(line.trim(), line.parse::<SnailTree>().unwrap().to_string());
+        }
+
+        assert!("[1,2".parse::<SnailTree>().is_err());
+        assert!("[1,2]]".parse::<SnailTree>().is_err());
+    }
+
+    #[test]
+    fn tree_reduction_log() {
+        let mut log = vec![];
+        let sum = "[[[[4,3],4],4],[7,[[8,4],9]]]"
+            .parse::<SnailTree>()
+            .unwrap()
+            .add(&"[1,1]".parse().unwrap(), Some(&mut log));
+
+        assert_eq!("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", sum.to_string());
+        assert_eq!(
+            log,
+            [
+                "after explode: [[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+                "after explode: [[[[0,7],4],[15,[0,13]]],[1,1]]",
+                "after split: [[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+                "after split: [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+                "after explode: [[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+            ]
+        );
+    }
+
+    #[test]
+    fn representations_agree() {
+        for input in [EXAMPLE, INPUT] {
+            let flat = parse_input(input).unwrap();
+            let trees = input
+                .trim()
+                .lines()
+                .map(|line| line.parse::<SnailTree>().unwrap())
+                .collect::<Vec<_>>();
+
+            let sum = trees[1..].iter().fold(trees[0].clone(), |sum, tree| sum.add(tree, None));
+            assert_eq!(part1(&flat), sum.magnitude());
+
+            let best = trees
+                .iter()
+                .permutations(2)
+                .map(|pair| pair[0].add(pair[1], None).magnitude())
+                .max()
+                .unwrap();
+            assert_eq!(part2(&flat), best);
+        }
+    }
 }
+
@@ -1,21 +1,11 @@
-use std::collections::HashMap;
+use aoc_common::automaton::{Automaton, Dense, Offsets};
 use std::error::Error;
-use std::ops::Range;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
 #[derive(Debug, Clone)]
 struct Image {
     algorithm: Vec<bool>,
-    image: HashMap<Point, bool>,
-    x: Range<i32>,
-    y: Range<i32>,
-    infinite: bool,
+    image: Vec<Vec<bool>>,
 }
 
 impl FromStr for Image {
@@ -30,103 +20,23 @@ impl FromStr for Image {
                 .chars()
                 .map(|symbol| symbol == '#')
                 .collect::<Vec<_>>();
-        let mut image = HashMap::new();
-        let mut y_max = 0;
-        let mut x_max = 0;
-        for (y, line) in lines.skip(1).enumerate() {
-            for (x, symbol) in line.char_indices() {
-                image.insert(
-                    Point {
-                        y: y as i32,
-                        x: x as i32,
-                    },
-                    symbol == '#',
-                );
-                x_max = x;
-            }
-            y_max = y;
-        }
-
-        Ok(Image {
-            image,
-            algorithm,
-            x: 0..(x_max+1) as i32,
-            y: 0..(y_max+1) as i32,
-            infinite: false,
-        })
-    }
-}
-
-impl Image {
-    fn get_pixel(&self, p: &Point) -> bool {
-        if self.x.contains(&p.x) && self.y.contains(&p.y) {
-            *self.image.get(p).unwrap_or(&false)
-        }
-        else {
-            self.infinite
-        }
-    }
-
-    fn get_pixel_area_as_number(&self, p: &Point) -> usize {
-        [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 0),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ]
-        .iter()
-        .map(|(dy, dx)| {
-            self.get_pixel(&Point {
-                y: p.y + dy,
-                x: p.x + dx,
-            }) as usize
-        })
-        .rev()
-        .enumerate()
-        .fold(0, |sum, (pos, value)| sum + value * (1 << pos))
-    }
 
-    fn enhance(&mut self) {
-        let mut new_image = self.image.clone();
+        let image = lines
+            .skip(1)
+            .map(|line| line.trim().chars().map(|symbol| symbol == '#').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
 
-        for y in self.y.start-2..self.y.end+2 {
-            for x in self.x.start-2..self.x.end+2 {
-                let point = Point{y, x};
-                new_image.insert(point.clone(), self.algorithm[self.get_pixel_area_as_number(&point)]);
-            }
-        }
-
-        self.x = self.x.start-2..self.x.end+2;
-        self.y = self.y.start-2..self.y.end+2;
-
-        if !self.infinite {
-            self.infinite = self.algorithm[0];
-        } else {
-            self.infinite = self.algorithm[(1<<9)-1];
-        }
-
-        self.image = new_image;
+        Ok(Image { algorithm, image })
     }
+}
 
-    fn active_pixel(&self) -> usize {
-        self.image.values().filter(|&&value| value).count()
-    }
+impl Image {
+    /// The infinite image as automaton, the background pixels may flip with every enhancement
+    fn enhancer(&self) -> Automaton<2, bool, Dense<2, bool>, Offsets<2>, impl Fn(bool, &[bool]) -> bool + '_> {
+        // The 3x3 area around a pixel (read row by row) is the binary index into the algorithm
+        let rule = |_, area: &[bool]| self.algorithm[area.iter().fold(0, |index, &pixel| index << 1 | pixel as usize)];
 
-    #[allow(dead_code)]
-    fn print(&self) -> String {
-        let mut pic = String::from("");
-        for y in self.y.start-2..self.y.end+2 {
-            for x in self.x.start-2..self.x.end+2 {
-                pic.push(if self.get_pixel(&Point{y,x}) {'#'} else {'.'});
-            }
-            pic.push('\n');
-        }
-        pic
+        Automaton::new(Dense::from_rows(self.image.clone()).growing(1), Offsets::cube(), rule, false)
     }
 }
 
@@ -153,20 +63,15 @@ pub fn main() {
 }
 
 fn part1(input: &Image) -> usize {
-    let mut image = input.clone();
-
-    image.enhance();
-    image.enhance();
-
-    image.active_pixel()
+    let mut image = input.enhancer();
+    image.run(2);
+    image.count(true).unwrap()
 }
 
 fn part2(input: &Image) -> usize {
-    let mut image = input.clone();
-    for _ in 0..50 {
-        image.enhance();
-    }
-    image.active_pixel()
+    let mut image = input.enhancer();
+    image.run(50);
+    image.count(true).unwrap()
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use ndarray::Array2;
+use aoc_common::bitgrid::BitGrid;
 use std::{error::Error, fmt, str::FromStr, string::ToString};
 use strum_macros::{Display, EnumString};
 
@@ -19,16 +19,24 @@ enum SeaCucumber {
     DBG,
 }
 
+/// Both herds as bit grids, so a whole herd can move in a few word operations
 #[derive(Debug, Clone)]
 struct SeaFloor {
-    floor: Array2<SeaCucumber>,
+    east: BitGrid,
+    south: BitGrid,
 }
 
 impl fmt::Display for SeaFloor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..self.floor.shape()[0] {
-            for x in 0..self.floor.shape()[1] {
-                write!(f, "{}", self.floor[[y, x]])?;
+        for y in 0..self.east.height() {
+            for x in 0..self.east.width() {
+                let cucumber = match (self.east.get(x, y), self.south.get(x, y)) {
+                    (true, false) => SeaCucumber::East,
+                    (false, true) => SeaCucumber::South,
+                    (false, false) => SeaCucumber::Empty,
+                    (true, true) => SeaCucumber::DBG,
+                };
+                write!(f, "{}", cucumber)?;
             }
             writeln!(f)?;
         }
@@ -46,60 +54,35 @@ impl FromStr for SeaFloor {
             .map(|line| {
                 line.chars()
                     .map(|c| c.to_string().parse::<SeaCucumber>())
-                    .collect::<Vec<_>>()
+                    .collect::<Result<Vec<_>, _>>()
             })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .map(|row| row.into_iter().collect::<Result<Vec<_>, _>>())
             .collect::<Result<Vec<_>, _>>()?;
 
+        let herd = |kind| {
+            data.iter()
+                .map(|row| row.iter().map(|cucumber| *cucumber == kind).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
         Ok(SeaFloor {
-            floor: Array2::from_shape_vec((data.len(), data[0].len()), data.iter().flatten().cloned().collect())?,
+            east: BitGrid::from_rows(&herd(SeaCucumber::East)),
+            south: BitGrid::from_rows(&herd(SeaCucumber::South)),
         })
     }
 }
 
 impl SeaFloor {
     fn step(&mut self) -> usize {
-        let mut move_count = 0;
-        let mut move_instr = vec![];
-
-        for (idx, &value) in self.floor.indexed_iter() {
-            if value == SeaCucumber::East && self.floor[self.wraped_idx((idx.0, idx.1 + 1))] == SeaCucumber::Empty {
-                move_instr.push(idx);
-            }
-        }
-
-        move_count += move_instr.len();
-
-        for idx in &move_instr {
-            self.floor[*idx] = SeaCucumber::Empty;
-            let new_pos = self.wraped_idx((idx.0, idx.1 + 1));
-            self.floor[new_pos] = SeaCucumber::East;
-        }
-
-        move_instr.clear();
+        // A cucumber can move if the cell in front of it (wrapping around) is empty
+        let empty = !&(&self.east | &self.south);
+        let moving_east = &self.east & &empty.rotated(-1, 0);
+        self.east = &self.east.and_not(&moving_east) | &moving_east.rotated(1, 0);
 
-        for (idx, &value) in self.floor.indexed_iter() {
-            if value == SeaCucumber::South && self.floor[self.wraped_idx((idx.0 + 1, idx.1))] == SeaCucumber::Empty {
-                move_instr.push(idx);
-            }
-        }
-
-        move_count += move_instr.len();
-
-        for idx in &move_instr {
-            self.floor[*idx] = SeaCucumber::Empty;
-            let new_pos = self.wraped_idx((idx.0 + 1, idx.1));
-            self.floor[new_pos] = SeaCucumber::South;
-        }
-
-        move_count
-    }
+        let empty = !&(&self.east | &self.south);
+        let moving_south = &self.south & &empty.rotated(0, -1);
+        self.south = &self.south.and_not(&moving_south) | &moving_south.rotated(0, 1);
 
-    fn wraped_idx(&self, idx: (usize, usize)) -> (usize, usize) {
-        let shape = self.floor.shape();
-        (idx.0 % shape[0], idx.1 % shape[1])
+        moving_east.count_ones() + moving_south.count_ones()
     }
 }
 
@@ -1,184 +1,389 @@
-use std::{cell::RefCell, rc::Rc};
+use std::fmt::{self, Display, Formatter};
 
 type NUMBER = u32;
-type FileTree<'a> = Rc<RefCell<FSEntry<'a>>>;
+
+pub type EntryId = usize;
+
+const ROOT: EntryId = 0;
+
+#[derive(Debug)]
+enum EntryKind {
+    File,
+    Directory { entries: Vec<EntryId> },
+}
 
 #[derive(Debug)]
-pub enum FSEntry<'a> {
-    File {
-        name: &'a str,
-        size: NUMBER,
+struct Entry<'a> {
+    name: &'a str,
+    parent: Option<EntryId>,
+    kind: EntryKind,
+    /// Size of the file, or the total size of everything inside the directory
+    size: NUMBER,
+}
+
+/// File system rebuilt from a terminal transcript, all entries live in one arena with the root at index 0
+#[derive(Debug)]
+pub struct FileSystem<'a> {
+    entries: Vec<Entry<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptError {
+    /// `cd` into a directory which was not listed before
+    UnknownDirectory {
+        line: usize,
+        name: String,
+    },
+    /// `cd ..` while already in `/`
+    AboveRoot {
+        line: usize,
     },
-    Directory {
-        name: &'a str,
-        entries: Vec<Rc<RefCell<FSEntry<'a>>>>,
+    /// A listed name already exists with a different type or size
+    Conflict {
+        line: usize,
+        name: String,
+    },
+    UnknownCommand {
+        line: usize,
+        command: String,
+    },
+    InvalidListing {
+        line: usize,
+        listing: String,
     },
 }
 
-impl FSEntry<'_> {
-    fn name(&self) -> &str {
-        match self {
-            FSEntry::File { name, size: _ } => name,
-            FSEntry::Directory { name, entries: _ } => name,
-        }
-    }
-
-    fn size(&self) -> NUMBER {
+impl Display for TranscriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            FSEntry::File { name: _, size } => *size,
-            FSEntry::Directory { name: _, entries } => entries.iter().map(|entry| entry.as_ref().borrow().size()).sum(),
+            TranscriptError::UnknownDirectory { line, name } => write!(f, "line {line}: unknown directory '{name}'"),
+            TranscriptError::AboveRoot { line } => write!(f, "line {line}: cannot leave the root directory"),
+            TranscriptError::Conflict { line, name } => {
+                write!(f, "line {line}: '{name}' was listed differently before")
+            }
+            TranscriptError::UnknownCommand { line, command } => write!(f, "line {line}: unknown command '{command}'"),
+            TranscriptError::InvalidListing { line, listing } => write!(f, "line {line}: invalid listing '{listing}'"),
         }
     }
 }
 
-pub fn parse_input(input: &str) -> FileTree {
-    let root = Rc::new(RefCell::new(FSEntry::Directory {
-        name: "/",
-        entries: vec![],
-    }));
-
-    let mut current = Rc::clone(&root);
-    let mut cwd = vec![current.clone()];
-
-    for line in input.trim().lines().skip(1) {
-        let line = line.trim();
-
-        if line == "$ ls" {
-            // Ignore
-            continue;
-        } else if line == "$ cd .." {
-            // Go up one directory
-            cwd.pop();
-            current = Rc::clone(cwd.last().unwrap());
-        } else if line.starts_with("$ cd ") {
-            // Go down one directory
-            let name = &line[5..];
-            let found4;
-
-            {
-                let current2 = &*current.as_ref().borrow();
-
-                match current2 {
-                    FSEntry::Directory { name: _, entries } => {
-                        let found2 = entries.iter().find(|entry| {
-                            let entry = &*entry.as_ref().borrow();
-                            match entry {
-                                FSEntry::Directory {
-                                    name: entry_name,
-                                    entries: _,
-                                } => *entry_name == name,
-                                FSEntry::File { name: _, size: _ } => false,
-                            }
-                        });
+impl std::error::Error for TranscriptError {}
+
+impl<'a> FileSystem<'a> {
+    pub fn from_transcript(transcript: &'a str) -> Result<Self, TranscriptError> {
+        let mut fs = FileSystem {
+            entries: vec![Entry {
+                name: "/",
+                parent: None,
+                kind: EntryKind::Directory { entries: vec![] },
+                size: 0,
+            }],
+        };
+        let mut cwd = ROOT;
+
+        for (idx, line) in transcript.trim().lines().enumerate() {
+            let line_nb = idx + 1;
+            let line = line.trim();
 
-                        if let Some(found3) = found2 {
-                            found4 = found3.clone();
-                        } else {
-                            panic!("unable to find directory: {}", name);
-                        }
+            if let Some(command) = line.strip_prefix("$ ") {
+                match command.split_once(' ') {
+                    None if command == "ls" => {}
+                    Some(("cd", "/")) => cwd = ROOT,
+                    Some(("cd", "..")) => {
+                        cwd = fs.entries[cwd]
+                            .parent
+                            .ok_or(TranscriptError::AboveRoot { line: line_nb })?;
+                    }
+                    Some(("cd", name)) => {
+                        cwd = fs.child(cwd, name).filter(|&child| fs.is_dir(child)).ok_or_else(|| {
+                            TranscriptError::UnknownDirectory {
+                                line: line_nb,
+                                name: name.to_string(),
+                            }
+                        })?;
+                    }
+                    _ => {
+                        return Err(TranscriptError::UnknownCommand {
+                            line: line_nb,
+                            command: command.to_string(),
+                        })
                     }
-                    _ => unreachable!("current should never be a file"),
                 }
+                continue;
             }
 
-            current = found4;
-
-            cwd.push(current.clone());
-        } else {
-            let current = &mut *current.as_ref().borrow_mut();
-
-            match current {
-                FSEntry::Directory {
-                    name: _,
-                    ref mut entries,
-                } => {
-                    if line.starts_with("dir") {
-                        // Add directory to current directory
-                        entries.push(Rc::new(RefCell::new(FSEntry::Directory {
-                            name: &line[4..],
-                            entries: vec![],
-                        })));
-                    } else {
-                        // Add file to current directory
-                        let parts = line
-                            .split_once(" ")
-                            .ok_or_else(|| format!("unable to parse file: {}", line))
-                            .unwrap();
-                        entries.push(
-                            Rc::new(RefCell::new(FSEntry::File {
-                                name: parts.1,
-                                size: parts.0.parse().unwrap(),
-                            }))
-                            .into(),
-                        );
-                    }
+            let invalid = || TranscriptError::InvalidListing {
+                line: line_nb,
+                listing: line.to_string(),
+            };
+            let (kind, size, name) = match line.split_once(' ').ok_or_else(invalid)? {
+                ("dir", name) => (EntryKind::Directory { entries: vec![] }, 0, name),
+                (size, name) => (EntryKind::File, size.parse().map_err(|_| invalid())?, name),
+            };
+
+            // Listing the same directory again is fine, as long as nothing changed
+            if let Some(existing) = fs.child(cwd, name) {
+                let same = match kind {
+                    EntryKind::File => !fs.is_dir(existing) && fs.size(existing) == size,
+                    EntryKind::Directory { .. } => fs.is_dir(existing),
+                };
+                if !same {
+                    return Err(TranscriptError::Conflict {
+                        line: line_nb,
+                        name: name.to_string(),
+                    });
                 }
-                _ => unreachable!("current should never be a file"),
+                continue;
             }
+
+            fs.add(cwd, name, kind, size);
         }
+
+        Ok(fs)
     }
 
-    root
-}
+    fn add(&mut self, parent: EntryId, name: &'a str, kind: EntryKind, size: NUMBER) {
+        let id = self.entries.len();
+        self.entries.push(Entry {
+            name,
+            parent: Some(parent),
+            kind,
+            size,
+        });
 
-pub fn part1(input: &FileTree) -> NUMBER {
-    let mut result = 0;
+        if let EntryKind::Directory { entries } = &mut self.entries[parent].kind {
+            entries.push(id);
+        }
 
-    let entry = &*input.as_ref().borrow();
-    match entry {
-        FSEntry::Directory { name: _, entries } => {
-            let directory_size = entry.size();
+        // Keep the cached sizes of all parent directories up to date
+        let mut dir = Some(parent);
+        while let Some(current) = dir {
+            self.entries[current].size += size;
+            dir = self.entries[current].parent;
+        }
+    }
 
-            if directory_size <= 100_000 {
-                result += directory_size;
-            }
+    fn children(&self, id: EntryId) -> &[EntryId] {
+        match &self.entries[id].kind {
+            EntryKind::Directory { entries } => entries,
+            EntryKind::File => &[],
+        }
+    }
 
-            for sub_entry in entries {
-                result += part1(sub_entry);
-            }
+    fn child(&self, id: EntryId, name: &str) -> Option<EntryId> {
+        self.children(id)
+            .iter()
+            .copied()
+            .find(|&child| self.entries[child].name == name)
+    }
+
+    pub fn root(&self) -> EntryId {
+        ROOT
+    }
+
+    pub fn name(&self, id: EntryId) -> &str {
+        self.entries[id].name
+    }
+
+    pub fn size(&self, id: EntryId) -> NUMBER {
+        self.entries[id].size
+    }
+
+    pub fn is_dir(&self, id: EntryId) -> bool {
+        matches!(self.entries[id].kind, EntryKind::Directory { .. })
+    }
+
+    /// Entry at an absolute path like `/a/e`
+    pub fn lookup(&self, path: &str) -> Option<EntryId> {
+        path.strip_prefix('/')?
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(ROOT, |dir, name| self.child(dir, name))
+    }
+
+    /// Absolute path of an entry, the inverse of `lookup`
+    pub fn path(&self, id: EntryId) -> String {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.entries[current].parent {
+            names.push(self.entries[current].name);
+            current = parent;
         }
-        _ => {}
+
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = EntryId> + '_ {
+        (0..self.entries.len()).filter(|&id| self.is_dir(id))
+    }
+
+    /// Directories with a total size of at most `threshold`
+    pub fn du_at_most(&self, threshold: NUMBER) -> impl Iterator<Item = EntryId> + '_ {
+        self.directories().filter(move |&id| self.size(id) <= threshold)
     }
 
-    result
+    /// Directories with a total size of at least `threshold`
+    pub fn du_at_least(&self, threshold: NUMBER) -> impl Iterator<Item = EntryId> + '_ {
+        self.directories().filter(move |&id| self.size(id) >= threshold)
+    }
+
+    /// Smallest directory which leaves at least `free` bytes on a disk of `capacity` bytes when deleted
+    pub fn smallest_freeing(&self, capacity: NUMBER, free: NUMBER) -> Option<EntryId> {
+        let missing = free.saturating_sub(capacity.saturating_sub(self.size(ROOT)));
+        self.du_at_least(missing).min_by_key(|&id| self.size(id))
+    }
+
+    fn fmt_entry(&self, id: EntryId, depth: usize, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_dir(id) { "dir" } else { "file" };
+        writeln!(
+            f,
+            "{:indent$}- {} ({}, size={})",
+            "",
+            self.name(id),
+            kind,
+            self.size(id),
+            indent = depth * 2
+        )?;
+
+        for &child in self.children(id) {
+            self.fmt_entry(child, depth + 1, f)?;
+        }
+        Ok(())
+    }
 }
 
-pub fn part2(input: &FileTree) -> NUMBER {
-    let root = &*input.as_ref().borrow();
+/// `tree` like listing in the format of the puzzle description, directories show their total size
+impl Display for FileSystem<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_entry(ROOT, 0, f)
+    }
+}
 
+pub fn parse_input(input: &str) -> FileSystem<'_> {
+    FileSystem::from_transcript(input).unwrap()
+}
+
+pub fn part1(fs: &FileSystem) -> NUMBER {
+    fs.du_at_most(100_000).map(|id| fs.size(id)).sum()
+}
+
+pub fn part2(fs: &FileSystem) -> NUMBER {
     const TOTAL_DISK_SPACE: NUMBER = 70_000_000;
     const REQUIRED_FREE_DISK_SPACE_FOR_UPDATE: NUMBER = 30_000_000;
 
-    // Get the disk usage
-    let disk_usage = root.size();
+    let dir = fs
+        .smallest_freeing(TOTAL_DISK_SPACE, REQUIRED_FREE_DISK_SPACE_FOR_UPDATE)
+        .unwrap();
+    fs.size(dir)
+}
 
-    // Calculate the amount of free disk space required
-    let required_free_disk_space = REQUIRED_FREE_DISK_SPACE_FOR_UPDATE - (TOTAL_DISK_SPACE - disk_usage);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Find the smallest directory must be deleted to free up enough space
-    *all_direcory_sizes(input)
-        .iter()
-        .filter(|&&size| size >= required_free_disk_space)
-        .min()
-        .unwrap()
-}
+    const EXAMPLE: &str = include_str!("../input/2022/day7_example.txt");
+    const INPUT: &str = include_str!("../input/2022/day7.txt");
 
-fn all_direcory_sizes<'a>(entry: &'a FileTree) -> Vec<NUMBER> {
-    let mut result = vec![];
+    #[test]
+    fn example_part1() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part1(&input), 95437);
+    }
 
-    let entry = &*entry.as_ref().borrow();
-    match entry {
-        FSEntry::Directory { name: _, entries } => {
-            let directory_size = entry.size();
+    #[test]
+    fn example_part2() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part2(&input), 24933642);
+    }
 
-            result.push(directory_size);
+    #[test]
+    fn input_part1() {
+        let input = parse_input(INPUT);
+        assert_eq!(part1(&input), 1315285);
+    }
 
-            for sub_entry in entries {
-                result.extend(all_direcory_sizes(sub_entry));
-            }
-        }
-        _ => {}
+    #[test]
+    fn input_part2() {
+        let input = parse_input(INPUT);
+        assert_eq!(part2(&input), 9847279);
     }
 
-    result
+    #[test]
+    fn lookup() {
+        let fs = parse_input(EXAMPLE);
+
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!(fs.size(e), 584);
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.size(fs.lookup("/d").unwrap()), 24933642);
+        assert_eq!(fs.size(fs.lookup("/").unwrap()), 48381165);
+        assert!(!fs.is_dir(fs.lookup("/a/e/i").unwrap()));
+        assert_eq!(fs.lookup("/a/x"), None);
+        assert_eq!(fs.lookup("a"), None);
+
+        let small = fs.du_at_most(100_000).map(|id| fs.path(id)).collect::<Vec<_>>();
+        assert_eq!(small, ["/a", "/a/e"]);
+        assert_eq!(fs.du_at_least(24_000_000).count(), 2);
+    }
+
+    #[test]
+    fn tree() {
+        let fs = parse_input(EXAMPLE);
+        let expected = "\
+- / (dir, size=48381165)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir, size=24933642)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+";
+        assert_eq!(fs.to_string(), expected);
+    }
+
+    #[test]
+    fn malformed_transcripts() {
+        assert_eq!(
+            FileSystem::from_transcript("$ cd /\n$ ls\ndir a\n$ cd b").unwrap_err(),
+            TranscriptError::UnknownDirectory {
+                line: 4,
+                name: "b".to_string()
+            }
+        );
+        assert_eq!(
+            FileSystem::from_transcript("$ cd /\n$ ls\n10 a\n$ cd a").unwrap_err(),
+            TranscriptError::UnknownDirectory {
+                line: 4,
+                name: "a".to_string()
+            }
+        );
+        assert_eq!(
+            FileSystem::from_transcript("$ cd /\n$ cd ..").unwrap_err(),
+            TranscriptError::AboveRoot { line: 2 }
+        );
+        assert!(matches!(
+            FileSystem::from_transcript("$ ls\n10 a\n$ ls\n20 a"),
+            Err(TranscriptError::Conflict { line: 4, .. })
+        ));
+        assert!(matches!(
+            FileSystem::from_transcript("$ rm -rf /"),
+            Err(TranscriptError::UnknownCommand { line: 1, .. })
+        ));
+        assert!(matches!(
+            FileSystem::from_transcript("$ ls\nten a"),
+            Err(TranscriptError::InvalidListing { line: 2, .. })
+        ));
+
+        // Listing a directory twice does not count its files twice
+        let fs = FileSystem::from_transcript("$ ls\n10 a\ndir b\n$ ls\n10 a\ndir b").unwrap();
+        assert_eq!(fs.size(fs.root()), 10);
+    }
 }
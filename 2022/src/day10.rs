@@ -1,5 +1,7 @@
 use std::str::FromStr;
 
+use aoc_common::ocr;
+
 type ParseResult = Vec<Instruction>;
 
 #[derive(Debug)]
@@ -84,21 +86,49 @@ pub fn part1(instructions: &ParseResult) -> isize {
 }
 
 pub fn part2(instructions: &ParseResult) -> String {
-    let mut display = vec![vec![' '; 40]; 6];
+    let mut display = vec![vec![false; 40]; 6];
 
     run_cpu(instructions, &mut |cycle, reg_x| {
-        let crt_pos = (cycle - 1) as isize % 40;
-
-        if ((crt_pos % 40) - reg_x).abs() <= 1 {
-            let y = (cycle / 40) as usize;
-            let x = (cycle - y*40) as usize;
+        let x = (cycle - 1) % 40;
+        let y = (cycle - 1) / 40;
 
-            display[y][x] = 'X';
+        if (x as isize - reg_x).abs() <= 1 {
+            display[y][x] = true;
         }
     });
 
-    let display = display.iter().map(|line| line.iter().collect::<String>()).collect::<Vec<String>>().join("\n");
-    let display = String::from("\n") + display.as_str() + "\n";
+    ocr::recognize_or_art(&display)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../input/2022/day10_example2.txt");
+    const INPUT: &str = include_str!("../input/2022/day10.txt");
+
+    #[test]
+    fn example_part1() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part1(&input), 13140);
+    }
+
+    #[test]
+    fn example_part2() {
+        // The example draws a test pattern instead of letters
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part2(&input).lines().nth(1), Some("##..##..##..##..##..##..##..##..##..##.."));
+    }
 
-    display
+    #[test]
+    fn input_part1() {
+        let input = parse_input(INPUT);
+        assert_eq!(part1(&input), 13520);
+    }
+
+    #[test]
+    fn input_part2() {
+        let input = parse_input(INPUT);
+        assert_eq!(part2(&input), "PGPHBEAB");
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::HashMap;
 
 use itertools::Itertools;
 use nom::{
@@ -12,7 +12,7 @@ use nom::{
     IResult,
 };
 
-type NUMBER = u128;
+type NUMBER = u64;
 type ParseResult = Vec<Monkey>;
 
 #[derive(Debug, Copy, Clone)]
@@ -25,12 +25,11 @@ enum MonkeyOperation {
 #[derive(Debug, Clone)]
 pub struct Monkey {
     number: usize,
-    items: VecDeque<NUMBER>,
+    items: Vec<NUMBER>,
     op: MonkeyOperation,
     divisor: NUMBER,
     condition_true_monkey: usize,
     condition_false_monkey: usize,
-    inspect_count: NUMBER,
 }
 
 impl Monkey {
@@ -40,9 +39,10 @@ impl Monkey {
 
         let (s, _) = multispace1(s)?;
         let (s, _) = tag("Starting items: ")(s)?;
-        let (s, items) = map_res(separated_list0(tuple((multispace0, tag(","), multispace0)), digit1), |v: Vec<&str>| {
-            v.iter().map(|s| s.parse::<NUMBER>()).collect()
-        })(s)?;
+        let (s, items) = map_res(
+            separated_list0(tuple((multispace0, tag(","), multispace0)), digit1),
+            |v: Vec<&str>| v.iter().map(|s| s.parse::<NUMBER>()).collect(),
+        )(s)?;
 
         let (s, _) = tuple((multispace1, tag("Operation: new = ")))(s)?;
         let (s, op) = MonkeyOperation::parse(s)?;
@@ -68,7 +68,6 @@ impl Monkey {
                 divisor,
                 condition_true_monkey,
                 condition_false_monkey,
-                inspect_count: 0,
             },
         ))
     }
@@ -93,10 +92,12 @@ impl MonkeyOperation {
         Ok((s, op))
     }
 
-    fn apply(&self, old: NUMBER) -> NUMBER {
+    /// Widened, so even squaring the largest worry level cannot overflow
+    fn apply(&self, old: NUMBER) -> u128 {
+        let old = u128::from(old);
         match self {
-            MonkeyOperation::Add(n) => old + n,
-            MonkeyOperation::Multiply(n) => old * n,
+            MonkeyOperation::Add(n) => old + u128::from(*n),
+            MonkeyOperation::Multiply(n) => old * u128::from(*n),
             MonkeyOperation::Quadruple => old * old,
         }
     }
@@ -126,62 +127,147 @@ impl std::fmt::Display for Monkey {
     }
 }
 
-fn simulate_turn(monkeys: &mut Vec<Monkey>, worry_fn: impl Fn(NUMBER) -> NUMBER) {
-    for idx in 0..monkeys.len() {
-        let monkey = &mut monkeys[idx];
-        let div = monkey.divisor;
-        let true_monkey = monkey.condition_true_monkey;
-        let false_monkey = monkey.condition_false_monkey;
-        let op = monkey.op;
-        let items = std::mem::replace(&mut monkey.items, VecDeque::new());
-
-        monkey.inspect_count += items.len() as NUMBER;
-
-        for item in items {
-            let item = worry_fn(op.apply(item));
-            let rx_monkey = if item % div == 0 { true_monkey } else { false_monkey };
-            monkeys[rx_monkey].items.push_back(item)
-        }
-    }
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Relief {
+    /// Worry levels are divided after every inspection, the numbers have to fit as they are
+    DivideBy(NUMBER),
+    /// Worry levels are only kept modulo the LCM of all divisors, which keeps every test result intact
+    None,
 }
 
-fn get_monkey_business(monkeys: &Vec<Monkey>) -> usize {
-    monkeys
-        .iter()
-        .map(|m| m.inspect_count)
-        .sorted()
-        .rev()
-        .take(2)
-        .product::<NUMBER>() as usize
+/// Items never interact with each other, so every item is followed on its own.
+///
+/// The state of an item at the start of a round is its monkey and worry level, once such a state
+/// repeats the item cycles forever. This makes any number of rounds cheap to count.
+pub struct Simulation<'a> {
+    monkeys: &'a [Monkey],
+    relief: Relief,
+    modulus: NUMBER,
 }
 
-pub fn part1(monkeys: &ParseResult) -> usize {
-    let mut monkeys = monkeys.clone();
+impl<'a> Simulation<'a> {
+    pub fn new(monkeys: &'a [Monkey], relief: Relief) -> Self {
+        assert!(monkeys.len() <= 64, "inspections are tracked as bit mask");
+        let modulus = monkeys.iter().map(|m| m.divisor).fold(1, num::integer::lcm);
 
-    for _ in 0..20 {
-        simulate_turn(&mut monkeys, |x| x / 3);
+        Simulation {
+            monkeys,
+            relief,
+            modulus,
+        }
     }
 
-    get_monkey_business(&monkeys)
-}
+    /// One round of a single item, returns its new state and a bit mask of the monkeys which inspected it
+    fn item_round(&self, (mut monkey, mut worry): (usize, NUMBER)) -> ((usize, NUMBER), u64) {
+        let mut inspected = 0;
+
+        // Monkeys take turns in order, so an item thrown to a later monkey is inspected again this round
+        loop {
+            let current = &self.monkeys[monkey];
+            inspected |= 1 << monkey;
+
+            let new = current.op.apply(worry);
+            worry = match self.relief {
+                Relief::DivideBy(divisor) => NUMBER::try_from(new / u128::from(divisor)).expect("worry level overflow"),
+                Relief::None => (new % u128::from(self.modulus)) as NUMBER,
+            };
+
+            let target = if worry % current.divisor == 0 {
+                current.condition_true_monkey
+            } else {
+                current.condition_false_monkey
+            };
+
+            if target <= monkey {
+                return ((target, worry), inspected);
+            }
+            monkey = target;
+        }
+    }
 
-pub fn part2(monkeys: &ParseResult) -> usize {
-    let mut monkeys = monkeys.clone();
+    fn items(&self) -> impl Iterator<Item = (usize, NUMBER)> + '_ {
+        self.monkeys
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, m)| m.items.iter().map(move |&worry| (idx, worry)))
+    }
 
-    let lcm = monkeys
-        .iter()
-        .map(|m| m.divisor)
-        .fold(1, |x, y| num::integer::lcm(x, y));
+    /// Inspections of every monkey per round, `timeline[round][monkey]`
+    pub fn timeline(&self, rounds: usize) -> Vec<Vec<NUMBER>> {
+        let mut timeline = vec![vec![0; self.monkeys.len()]; rounds];
 
-    for _ in 0..10_000 {
-        simulate_turn(&mut monkeys, |x| x % lcm);
+        for mut item in self.items() {
+            for counts in timeline.iter_mut() {
+                let (next, inspected) = self.item_round(item);
+                add_inspections(counts, inspected, 1);
+                item = next;
+            }
+        }
+
+        timeline
+    }
+
+    /// Total inspections of every monkey after `rounds` rounds
+    pub fn inspections(&self, rounds: usize) -> Vec<NUMBER> {
+        let mut counts = vec![0; self.monkeys.len()];
+
+        for mut item in self.items() {
+            let mut seen = HashMap::new();
+            let mut history = vec![];
+
+            while history.len() < rounds {
+                if let Some(&start) = seen.get(&item) {
+                    // Everything from `start` on repeats, count whole cycles at once
+                    let cycle = &history[start..];
+                    let remaining = rounds - history.len();
+
+                    for &inspected in cycle {
+                        add_inspections(&mut counts, inspected, (remaining / cycle.len()) as NUMBER);
+                    }
+                    for &inspected in &cycle[..remaining % cycle.len()] {
+                        add_inspections(&mut counts, inspected, 1);
+                    }
+                    break;
+                }
+
+                seen.insert(item, history.len());
+                let (next, inspected) = self.item_round(item);
+                add_inspections(&mut counts, inspected, 1);
+                history.push(inspected);
+                item = next;
+            }
+        }
+
+        counts
     }
+}
+
+fn add_inspections(counts: &mut [NUMBER], inspected: u64, times: NUMBER) {
+    for (monkey, count) in counts.iter_mut().enumerate() {
+        if inspected & (1 << monkey) != 0 {
+            *count += times;
+        }
+    }
+}
 
-    get_monkey_business(&monkeys)
+fn get_monkey_business(inspections: &[NUMBER]) -> usize {
+    inspections.iter().sorted().rev().take(2).product::<NUMBER>() as usize
+}
+
+pub fn part1(monkeys: &ParseResult) -> usize {
+    let simulation = Simulation::new(monkeys, Relief::DivideBy(3));
+    get_monkey_business(&simulation.inspections(20))
+}
+
+pub fn part2(monkeys: &ParseResult) -> usize {
+    let simulation = Simulation::new(monkeys, Relief::None);
+    get_monkey_business(&simulation.inspections(10_000))
 }
 
 #[cfg(test)]
 mod tests {
+    use num::{BigUint, Zero};
+
     use super::*;
 
     const INPUT: &str = include_str!("../input/2022/day11.txt");
@@ -198,4 +284,80 @@ mod tests {
         assert_eq!(part2(&parse_input(INPUT_EXAMPLE)), 2713310158);
         assert_eq!(part2(&parse_input(INPUT)), 14636993466);
     }
+
+    #[test]
+    fn timeline() {
+        let monkeys = parse_input(INPUT_EXAMPLE);
+        let simulation = Simulation::new(&monkeys, Relief::None);
+
+        let timeline = simulation.timeline(20);
+        assert_eq!(timeline[0], [2, 4, 3, 6]);
+
+        let totals = timeline.iter().fold(vec![0; 4], |totals, round| {
+            totals.iter().zip(round).map(|(a, b)| a + b).collect()
+        });
+        assert_eq!(totals, [99, 97, 8, 103]);
+        assert_eq!(simulation.inspections(20), totals);
+    }
+
+    #[test]
+    fn many_rounds() {
+        let monkeys = parse_input(INPUT_EXAMPLE);
+        let simulation = Simulation::new(&monkeys, Relief::None);
+
+        assert_eq!(simulation.inspections(1000), [5204, 4792, 199, 5192]);
+        assert_eq!(simulation.inspections(10_000), [52166, 47830, 1938, 52013]);
+
+        // Counted through the cycles, the same as following every round
+        let rounds = 1_000_000;
+        let totals = simulation.timeline(rounds).iter().fold(vec![0; 4], |totals, round| {
+            totals.iter().zip(round).map(|(a, b)| a + b).collect()
+        });
+        assert_eq!(simulation.inspections(rounds), totals);
+    }
+
+    /// Exact worry levels without any modulus, monkey by monkey like in the puzzle, only feasible for a few rounds
+    fn exact_timeline(monkeys: &[Monkey], rounds: usize) -> Vec<Vec<NUMBER>> {
+        let mut items = monkeys
+            .iter()
+            .map(|m| m.items.iter().map(|&worry| BigUint::from(worry)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut timeline = vec![];
+
+        for _ in 0..rounds {
+            let mut counts = vec![0; monkeys.len()];
+            for (idx, monkey) in monkeys.iter().enumerate() {
+                for worry in std::mem::take(&mut items[idx]) {
+                    counts[idx] += 1;
+                    let worry = match monkey.op {
+                        MonkeyOperation::Add(n) => worry + n,
+                        MonkeyOperation::Multiply(n) => worry * n,
+                        MonkeyOperation::Quadruple => &worry * &worry,
+                    };
+                    let target = if (&worry % monkey.divisor).is_zero() {
+                        monkey.condition_true_monkey
+                    } else {
+                        monkey.condition_false_monkey
+                    };
+                    items[target].push(worry);
+                }
+            }
+            timeline.push(counts);
+        }
+
+        timeline
+    }
+
+    #[test]
+    fn large_divisors() {
+        // The LCM is above 2^32, so squaring a worry level does not have to fit into 64 bits
+        let input = INPUT_EXAMPLE
+            .replace("divisible by 23", "divisible by 4294967311")
+            .replace("79, 60, 97", "79, 60, 9999999967");
+        let monkeys = parse_input(&input);
+        let simulation = Simulation::new(&monkeys, Relief::None);
+        assert!(simulation.modulus > 1 << 32);
+
+        assert_eq!(simulation.timeline(12), exact_timeline(&monkeys, 12));
+    }
 }
@@ -1,11 +1,12 @@
 use std::cmp::Ordering;
-use std::fmt::{Debug, Formatter};
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{digit1, line_ending},
-    combinator::{map, map_res},
+    combinator::{all_consuming, map, map_res},
     multi::{separated_list0, separated_list1},
     sequence::{delimited, separated_pair, tuple},
     IResult,
@@ -45,51 +46,56 @@ pub fn parse_input(input: &str) -> Vec<(Packet, Packet)> {
     separated_list1(tuple((line_ending, line_ending)), parse_pair)(input.trim()).unwrap().1
 }
 
+impl FromStr for Packet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        all_consuming(Packet::parse)(s)
+            .map(|(_, packet)| packet)
+            .map_err(|e| format!("Invalid packet '{}': {}", s, e))
+    }
+}
+
+/// Packets compare by their puzzle ordering, so `1` and `[1]` are equal
 impl PartialEq for Packet {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Packet::Number(nb1), Packet::Number(nb2)) => nb1 == nb2,
-            (Packet::List(packets1), Packet::List(packets2)) => {
-                for (packet1, packet2) in packets1.iter().zip(packets2) {
-                    if !packet1.eq(packet2) {
-                        return false;
-                    }
-                }
-                true
-            }
-            (Packet::Number(_), Packet::List(_)) => Packet::List(vec![self.clone()]).eq(other),
-            (Packet::List(_), Packet::Number(_)) => self.eq(&Packet::List(vec![other.clone()])),
-        }
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Packet::Number(nb1), Packet::Number(nb2)) => nb1.partial_cmp(nb2),
-            (Packet::List(packets1), Packet::List(packets2)) => {
-                for idx in 0..packets1.len() {
-                    if let Some(p2) = packets2.get(idx) {
-                        if let Some(ordering) = packets1[idx].partial_cmp(p2) {
-                            if ordering != Ordering::Equal {
-                                return Some(ordering);
-                            }
-                        } else {
-                            return Some(Ordering::Less);
-                        }
-                    } else {
-                        return Some(Ordering::Greater);
-                    }
-                }
+            (Packet::Number(nb1), Packet::Number(nb2)) => nb1.cmp(nb2),
+            // Compares element wise, the shorter list is smaller if all elements are equal
+            (Packet::List(packets1), Packet::List(packets2)) => packets1.iter().cmp(packets2),
+            (Packet::Number(_), Packet::List(packets)) => std::slice::from_ref(self).iter().cmp(packets),
+            (Packet::List(packets), Packet::Number(_)) => packets.iter().cmp(std::slice::from_ref(other)),
+        }
+    }
+}
 
-                if packets1.len() < packets2.len() {
-                    return Some(Ordering::Less);
+/// Same notation as the puzzle input, parses back to the identical packet
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Packet::Number(nb) => write!(f, "{}", nb),
+            Packet::List(packets) => {
+                write!(f, "[")?;
+                for (idx, packet) in packets.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", packet)?;
                 }
-
-                Some(Ordering::Equal)
+                write!(f, "]")
             }
-            (Packet::Number(_), Packet::List(_)) => Packet::List(vec![self.clone()]).partial_cmp(other),
-            (Packet::List(_), Packet::Number(_)) => self.partial_cmp(&Packet::List(vec![other.clone()])),
         }
     }
 }
@@ -118,25 +124,135 @@ pub fn part1(pairs: &ParseResult) -> usize {
         .sum()
 }
 
-pub fn part2(pairs: &ParseResult) -> usize {
+/// All packets and the two divider packets in order, flagged if they are a divider
+fn sorted_with_dividers(pairs: &ParseResult) -> Vec<(Packet, bool)> {
     let mut packets = Vec::with_capacity(pairs.len() * 2 + 2);
 
-    let driver1 = Packet::parse("[[2]]").unwrap().1;
-    let driver2 = Packet::parse("[[6]]").unwrap().1;
-
-    packets.push((&driver1, true));
-    packets.push((&driver2, true));
+    packets.push(("[[2]]".parse().unwrap(), true));
+    packets.push(("[[6]]".parse().unwrap(), true));
 
     for (p1, p2) in pairs {
-        packets.push((p1, false));
-        packets.push((p2, false));
+        packets.push((p1.clone(), false));
+        packets.push((p2.clone(), false));
     }
 
-    packets.sort_unstable_by(|(p1, _), (p2, _)| p1.partial_cmp(p2).unwrap());
-
+    packets.sort_by(|(p1, _), (p2, _)| p1.cmp(p2));
     packets
+}
+
+/// One packet per line in sorted order, divider packets are marked
+pub fn sorted_dump(pairs: &ParseResult) -> String {
+    sorted_with_dividers(pairs)
+        .iter()
+        .enumerate()
+        .map(|(idx, (packet, is_divider))| {
+            let marker = if *is_divider { "  <- divider" } else { "" };
+            format!("{:>4}: {}{}", idx + 1, packet, marker)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn part2(pairs: &ParseResult) -> usize {
+    sorted_with_dividers(pairs)
         .iter()
         .enumerate()
-        .filter_map(|(idx, &(_, is_driver))| if is_driver { Some(idx + 1) } else { None })
+        .filter_map(|(idx, &(_, is_divider))| if is_divider { Some(idx + 1) } else { None })
         .product()
 }
+
+#[cfg(test)]
+mod tests {
+    use aoc_common::random::Xorshift;
+
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../input/2022/day13_example.txt");
+    const INPUT: &str = include_str!("../input/2022/day13.txt");
+
+    /// Small numbers and short lists, so equal prefixes and `1` vs `[1]` comparisons are common
+    fn random_packet(rng: &mut Xorshift, depth: usize) -> Packet {
+        if depth == 0 || rng.next(3) == 0 {
+            Packet::Number(rng.next(4) as Number)
+        } else {
+            let len = rng.next(4) as usize;
+            Packet::List((0..len).map(|_| random_packet(rng, depth - 1)).collect())
+        }
+    }
+
+    #[test]
+    fn example_part1() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part1(&input), 13);
+    }
+
+    #[test]
+    fn example_part2() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part2(&input), 140);
+    }
+
+    #[test]
+    fn input_part1() {
+        let input = parse_input(INPUT);
+        assert_eq!(part1(&input), 5882);
+    }
+
+    #[test]
+    fn input_part2() {
+        let input = parse_input(INPUT);
+        assert_eq!(part2(&input), 24948);
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for line in INPUT.lines().chain(EXAMPLE.lines()).filter(|l| !l.is_empty()) {
+            let packet = line.parse::<Packet>().unwrap();
+            assert_eq!(packet.to_string(), line.trim());
+        }
+
+        let mut rng = Xorshift::new(0x2022_1213);
+        for _ in 0..1000 {
+            let packet = random_packet(&mut rng, 4);
+            let parsed = packet.to_string().parse::<Packet>().unwrap();
+            assert_eq!(parsed.to_string(), packet.to_string());
+        }
+
+        assert!("[1,2".parse::<Packet>().is_err());
+        assert!("[1,2]]".parse::<Packet>().is_err());
+    }
+
+    #[test]
+    fn ordering_properties() {
+        let mut rng = Xorshift::new(0x13);
+        let packets = (0..60).map(|_| random_packet(&mut rng, 3)).collect::<Vec<_>>();
+
+        for a in &packets {
+            assert_eq!(a.cmp(a), Ordering::Equal);
+            for b in &packets {
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "antisymmetry of {} and {}", a, b);
+                assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+                for c in &packets {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "transitivity of {} <= {} <= {}", a, b, c);
+                    }
+                }
+            }
+        }
+
+        assert_eq!("[1]".parse::<Packet>().unwrap(), "1".parse().unwrap());
+        assert!("[1]".parse::<Packet>().unwrap() < "[1,2]".parse().unwrap());
+    }
+
+    #[test]
+    fn dump() {
+        let dump = sorted_dump(&parse_input(EXAMPLE));
+        let lines = dump.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 18);
+        assert_eq!(lines[0], "   1: []");
+        assert_eq!(lines[9], "  10: [[2]]  <- divider");
+        assert_eq!(lines[13], "  14: [[6]]  <- divider");
+        assert_eq!(lines[17], "  18: [9]");
+    }
+}
@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::ops::Add;
 
+use aoc_common::render::{Frame, Palette, Recorder, BLACK};
 use itertools::Itertools;
 use nom::bytes::complete::tag;
 use nom::character::complete::{line_ending, i32};
@@ -11,6 +13,7 @@ use crate::utils::ws;
 
 type Number = i32;
 type ParseResult = Vec<Vec<Coordinate>>;
+type Pic = HashMap<Coordinate, Content>;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Coordinate {
@@ -46,11 +49,17 @@ pub fn parse_input(input: &str) -> ParseResult {
 }
 
 pub fn part1(walls: &ParseResult) -> isize {
-    // Build pic of walls from coordinates
-    let mut pic = std::collections::HashMap::new();
-    let spawn = Coordinate { x: 500, y: 0 };
+    pour_sand(walls, false, |_| {})
+}
+
+pub fn part2(walls: &ParseResult) -> isize {
+    pour_sand(walls, true, |_| {})
+}
 
-    let mut max_y = std::i32::MIN;
+/// Build pic of walls from coordinates, also returns the lowest wall
+fn build_pic(walls: &ParseResult) -> (Pic, Number) {
+    let mut pic = HashMap::new();
+    let mut max_y = Number::MIN;
 
     walls.iter().for_each(|wall| {
         wall.iter().tuple_windows().for_each(|(a, b)| {
@@ -63,12 +72,30 @@ pub fn part1(walls: &ParseResult) -> isize {
         });
     });
 
+    (pic, max_y)
+}
+
+/// Returns the number of resting sand units, `on_rest` is called after every unit came to rest.
+///
+/// Without `infinite_floor` sand falls into the abyss below the lowest wall (part1), with it
+/// the sand piles up on a floor two below the lowest wall until the spawn is blocked (part2).
+fn pour_sand(walls: &ParseResult, infinite_floor: bool, mut on_rest: impl FnMut(&Pic)) -> isize {
+    let (mut pic, max_y) = build_pic(walls);
+    let spawn = Coordinate { x: 500, y: 0 };
+
     // Fill pic with sand
     'spawn_new_sand: for unit in 1.. {
         let mut sand_pos = spawn;
 
         loop {
-            if sand_pos.y > max_y {
+            if infinite_floor && sand_pos.y == max_y + 1 {
+                // Reached infinite bottom wall
+                pic.insert(sand_pos, Content::Sand);
+                on_rest(&pic);
+                continue 'spawn_new_sand;
+            }
+
+            if !infinite_floor && sand_pos.y > max_y {
                 return unit - 1;
             }
 
@@ -82,11 +109,13 @@ pub fn part1(walls: &ParseResult) -> isize {
                 sand_pos = sand_pos + Coordinate {x: 1, y: 1};
             }
             else {
+                if sand_pos == spawn {
+                    return unit;
+                }
+
                 // Sand has come to rest
                 pic.insert(sand_pos, Content::Sand);
-                // println!("Unit: {}", unit);
-                // print_pic(&pic);
-                // println!();
+                on_rest(&pic);
                 continue 'spawn_new_sand;
             }
         }
@@ -96,7 +125,7 @@ pub fn part1(walls: &ParseResult) -> isize {
 }
 
 #[allow(dead_code)]
-fn print_pic(pic: &std::collections::HashMap<Coordinate, Content>) {
+fn print_pic(pic: &Pic) {
     let (min_x, max_x) = pic.keys().minmax_by_key(|k| k.x).into_option().unwrap();
     let (min_y, max_y) = pic.keys().minmax_by_key(|k| k.y).into_option().unwrap();
 
@@ -115,60 +144,33 @@ fn print_pic(pic: &std::collections::HashMap<Coordinate, Content>) {
     }
 }
 
-pub fn part2(walls: &ParseResult) -> isize {
-    // Build pic of walls from coordinates
-    let mut pic = std::collections::HashMap::new();
-    let spawn = Coordinate { x: 500, y: 0 };
-
-    let mut max_y = std::i32::MIN;
-
-    walls.iter().for_each(|wall| {
-        wall.iter().tuple_windows().for_each(|(a, b)| {
-            for x in a.x.min(b.x)..=a.x.max(b.x) {
-                for y in a.y.min(b.y)..=a.y.max(b.y) {
-                    max_y = max_y.max(y);
-                    pic.insert(Coordinate {x, y}, Content::Rock);
-                }
+/// Record the falling sand as animated gif, e.g. `record_sand(&walls, true, "day14.gif", 10)`
+pub fn record_sand(walls: &ParseResult, infinite_floor: bool, path: &str, every: usize) -> std::io::Result<()> {
+    let (_, max_y) = build_pic(walls);
+
+    // The sand can at most spread as wide as the pile gets high
+    let min_x = walls.iter().flatten().map(|c| c.x).min().unwrap().min(500 - max_y - 2);
+    let max_x = walls.iter().flatten().map(|c| c.x).max().unwrap().max(500 + max_y + 2);
+    let (width, height) = ((max_x - min_x + 1) as usize, (max_y + 2) as usize);
+
+    let palette = Palette::new(BLACK)
+        .with(Content::Rock, [128, 128, 128])
+        .with(Content::Sand, [194, 178, 128]);
+
+    let mut recorder = Recorder::new().every(every);
+    pour_sand(
+        walls,
+        infinite_floor,
+        recorder.hook(|pic: &Pic| {
+            let mut frame = Frame::new(width, height, BLACK);
+            for (pos, content) in pic {
+                frame.set((pos.x - min_x) as usize, pos.y as usize, palette.color(content));
             }
-        });
-    });
-
-    // Fill pic with sand
-    'spawn_new_sand: for unit in 1.. {
-        let mut sand_pos = spawn;
+            frame.scaled(2)
+        }),
+    );
 
-        loop {
-            if sand_pos.y == max_y + 1 {
-                // Reached infinite bottom wall
-                pic.insert(sand_pos, Content::Sand);
-                continue 'spawn_new_sand;
-            }
-
-            if !pic.contains_key(&(sand_pos + Coordinate {x: 0, y: 1})) {
-                sand_pos = sand_pos + Coordinate {x: 0, y: 1};
-            }
-            else if !pic.contains_key(&(sand_pos + Coordinate {x: -1, y: 1})) {
-                sand_pos = sand_pos + Coordinate {x: -1, y: 1};
-            }
-            else if !pic.contains_key(&(sand_pos + Coordinate {x: 1, y: 1})) {
-                sand_pos = sand_pos + Coordinate {x: 1, y: 1};
-            }
-            else {
-                if sand_pos == spawn {
-                    return unit;
-                }
-
-                // Sand has come to rest
-                pic.insert(sand_pos, Content::Sand);
-                // println!("Unit: {}", unit);
-                // print_pic(&pic);
-                // println!();
-                continue 'spawn_new_sand;
-            }
-        }
-    }
-
-    unreachable!()
+    recorder.write_gif(path, 20)
 }
 
 #[cfg(test)]
@@ -201,4 +203,19 @@ mod tests {
         let input = parse_input(INPUT);
         assert_eq!(part2(&input), 26484);
     }
+
+    #[test]
+    fn record_gif() {
+        let dir = std::env::temp_dir().join(format!("aoc-2022-day14-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sand.gif");
+
+        record_sand(&parse_input(EXAMPLE), false, path.to_str().unwrap(), 5).unwrap();
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let gif = std::fs::read(&path).unwrap();
+        assert!(gif.starts_with(b"GIF89a"), "not a gif: {:?}", &gif[..6]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
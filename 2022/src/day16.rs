@@ -1,4 +1,5 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
 use nom::{
     bytes::complete::tag,
@@ -61,47 +62,248 @@ pub fn parse_input(input: &str) -> ParseResult {
         .collect()
 }
 
-fn dfs(graph: &ParseResult, minute: i8, current: &Valve, open_valves: &HashSet<String>) -> u32 {
-    if minute <= 0 || open_valves.is_empty() {
-        return 0;
+/// Valves worth opening, connected by the length of the shortest path between them
+pub struct Network {
+    names: Vec<String>,
+    flow_rates: Vec<u32>,
+    /// `distances[from][to]` in minutes, the start valve is the last one
+    distances: Vec<Vec<u32>>,
+}
+
+/// Opening order of one agent, every valve with the minute it is opened in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Openings(Vec<(String, u32)>);
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub pressure: u32,
+    pub agents: Vec<Openings>,
+}
+
+/// Best pressure of every set of opened valves, with the order to open them in
+type BestPerSubset = Vec<Option<(u32, Vec<usize>)>>;
+
+impl Network {
+    /// Floyd–Warshall over all valves, then only valves with a flow rate and `start` are kept
+    pub fn compress(valves: &ParseResult, start: &str) -> Self {
+        let mut all = valves.keys().map(String::as_str).collect::<Vec<_>>();
+        all.sort_unstable();
+        let index: HashMap<_, _> = all.iter().enumerate().map(|(idx, name)| (*name, idx)).collect();
+
+        let mut dist = vec![vec![u32::MAX / 2; all.len()]; all.len()];
+        for (idx, name) in all.iter().enumerate() {
+            dist[idx][idx] = 0;
+            for tunnel in &valves[*name].tunnels_to {
+                dist[idx][index[tunnel.as_str()]] = 1;
+            }
+        }
+        for via in 0..all.len() {
+            for from in 0..all.len() {
+                for to in 0..all.len() {
+                    dist[from][to] = dist[from][to].min(dist[from][via] + dist[via][to]);
+                }
+            }
+        }
+
+        let mut kept = all
+            .iter()
+            .copied()
+            .filter(|name| valves[*name].flow_rate > 0)
+            .collect::<Vec<_>>();
+        assert!(kept.len() <= 20, "every subset of valves is stored");
+        kept.push(start);
+
+        Network {
+            names: kept.iter().map(|name| name.to_string()).collect(),
+            flow_rates: kept.iter().map(|name| valves[*name].flow_rate as u32).collect(),
+            distances: kept
+                .iter()
+                .map(|from| kept.iter().map(|to| dist[index[from]][index[to]]).collect())
+                .collect(),
+        }
+    }
+
+    fn valve_count(&self) -> usize {
+        self.names.len() - 1
+    }
+
+    fn best_per_subset(&self, minutes: u32) -> BestPerSubset {
+        let mut best = vec![None; 1 << self.valve_count()];
+        self.search(self.valve_count(), minutes, 0, 0, &mut vec![], &mut best);
+        best
+    }
+
+    fn search(
+        &self,
+        current: usize,
+        time_left: u32,
+        opened: usize,
+        pressure: u32,
+        order: &mut Vec<usize>,
+        best: &mut BestPerSubset,
+    ) {
+        if best[opened].as_ref().is_none_or(|(known, _)| pressure > *known) {
+            best[opened] = Some((pressure, order.clone()));
+        }
+
+        for next in 0..self.valve_count() {
+            // Walking there and opening it has to leave some time for the flow
+            let cost = self.distances[current][next] + 1;
+            if opened & (1 << next) != 0 || cost >= time_left {
+                continue;
+            }
+
+            let time_left = time_left - cost;
+            order.push(next);
+            self.search(
+                next,
+                time_left,
+                opened | 1 << next,
+                pressure + self.flow_rates[next] * time_left,
+                order,
+                best,
+            );
+            order.pop();
+        }
+    }
+
+    fn openings(&self, order: &[usize]) -> Openings {
+        let mut minute = 0;
+        let mut current = self.valve_count();
+
+        Openings(
+            order
+                .iter()
+                .map(|&valve| {
+                    minute += self.distances[current][valve] + 1;
+                    current = valve;
+                    (self.names[valve].clone(), minute)
+                })
+                .collect(),
+        )
     }
 
-    let mut max_score = 0;
+    pub fn single(&self, minutes: u32) -> Plan {
+        let (pressure, order) = self
+            .best_per_subset(minutes)
+            .into_iter()
+            .flatten()
+            .max_by_key(|(p, _)| *p)
+            .unwrap();
 
-    for tunnel in &current.tunnels_to {
-        let next = &graph[tunnel];
+        Plan {
+            pressure,
+            agents: vec![self.openings(&order)],
+        }
+    }
 
-        let mut with_current_valve_open = [0, 0];
-        if open_valves.contains(&current.name) {
-            //dbg!(minute, &current.name, &next.name, closed_valves, &scores);
+    /// Both agents open disjoint sets of valves, so the best pair of disjoint subsets is the answer
+    pub fn with_elephant(&self, minutes: u32) -> Plan {
+        let best = self.best_per_subset(minutes);
 
-            // Current valve is still closed
-            let open_valves = &mut open_valves.clone();
-            open_valves.remove(&current.name);
-            with_current_valve_open[0] =
-                (current.flow_rate * (minute - 1) as u16) as u32 + dfs(graph, minute - 2_i8, next, &open_valves);
+        // Best result for each subset using any of its valves
+        let mut within = best.clone();
+        for mask in 0..within.len() {
+            for bit in 0..self.valve_count() {
+                if mask & (1 << bit) != 0 {
+                    let smaller = &within[mask ^ (1 << bit)];
+                    if smaller.as_ref().map(|(p, _)| *p) > within[mask].as_ref().map(|(p, _)| *p) {
+                        within[mask] = smaller.clone();
+                    }
+                }
+            }
         }
-        else {
-            with_current_valve_open[1] = dfs(graph, minute - 1, next, open_valves);
+
+        let all = within.len() - 1;
+        let (you, elephant) = best
+            .iter()
+            .enumerate()
+            .filter_map(|(mask, you)| Some((you.as_ref()?, within[all ^ mask].as_ref()?)))
+            .max_by_key(|(you, elephant)| you.0 + elephant.0)
+            .unwrap();
+
+        Plan {
+            pressure: you.0 + elephant.0,
+            agents: vec![self.openings(&you.1), self.openings(&elephant.1)],
         }
+    }
+}
 
-        max_score = max_score.max(with_current_valve_open[0].max(with_current_valve_open[1]));
+impl Display for Openings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let valves = self
+            .0
+            .iter()
+            .map(|(name, minute)| format!("{name}@{minute}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", valves.join(" -> "))
     }
+}
 
-    max_score
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "pressure released: {}", self.pressure)?;
+        for (idx, openings) in self.agents.iter().enumerate() {
+            writeln!(f, "agent {}: {}", idx + 1, openings)?;
+        }
+        Ok(())
+    }
 }
 
 pub fn part1(input: &ParseResult) -> u32 {
-    let a = input
-        .iter()
-        .filter(|valve| valve.1.flow_rate != 0)
-        .map(|valve| valve.0)
-        .cloned();
+    Network::compress(input, "AA").single(30).pressure
+}
 
-    dfs(input, 19, &input["AA"], &HashSet::from_iter(a))
+pub fn part2(input: &ParseResult) -> u32 {
+    Network::compress(input, "AA").with_elephant(26).pressure
 }
 
-pub fn part2(input: &ParseResult) -> isize {
-    _= input;
-    42
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../input/2022/day16_example.txt");
+    const INPUT: &str = include_str!("../input/2022/day16.txt");
+
+    #[test]
+    fn example_part1() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part1(&input), 1651);
+    }
+
+    #[test]
+    fn example_part2() {
+        let input = parse_input(EXAMPLE);
+        assert_eq!(part2(&input), 1707);
+    }
+
+    #[test]
+    fn input_part1() {
+        let input = parse_input(INPUT);
+        assert_eq!(part1(&input), 1947);
+    }
+
+    #[test]
+    fn input_part2() {
+        let input = parse_input(INPUT);
+        assert_eq!(part2(&input), 2556);
+    }
+
+    #[test]
+    fn opening_order() {
+        let network = Network::compress(&parse_input(EXAMPLE), "AA");
+        assert_eq!(network.valve_count(), 6);
+        assert_eq!(network.distances[network.valve_count()][0], 1);
+
+        let plan = network.single(30);
+        assert_eq!(
+            plan.agents[0].to_string(),
+            "DD@2 -> BB@5 -> JJ@9 -> HH@17 -> EE@21 -> CC@24"
+        );
+
+        let plan = network.with_elephant(26);
+        let mut orders = plan.agents.iter().map(|agent| agent.to_string()).collect::<Vec<_>>();
+        orders.sort();
+        assert_eq!(orders, ["DD@2 -> HH@7 -> EE@11", "JJ@3 -> BB@7 -> CC@9"]);
+    }
 }
@@ -1,311 +1,273 @@
-use std::ops::{Add, AddAssign, Sub};
+use std::fmt::{Display, Formatter};
 
 use nom::{
     bytes::complete::tag,
-    character::complete::{line_ending, multispace1, space0, space1, u8},
-    combinator::opt,
+    character::complete::{alpha1, multispace0, multispace1, space1, u16, u8},
     multi::{many1, separated_list1},
-    sequence::{delimited, preceded, terminated, tuple},
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
     IResult,
 };
-use rayon::prelude::IntoParallelRefIterator;
-use rayon::{iter::ParallelIterator, prelude::IndexedParallelIterator};
+use rayon::prelude::*;
 
 use crate::utils::ws;
 
-type Number = u8;
-type ParseResult = Vec<Blueprint>;
+type Number = u16;
+type Resources<const N: usize> = [Number; N];
+type ParseResult = Vec<Blueprint<4>>;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-struct Values {
-    ore: Number,
-    clay: Number,
-    obsidian: Number,
-    geode: Number,
-}
-
-#[derive(Copy, Clone, PartialEq, Eq)]
-struct Step {
-    resources: Values,
-    robots: Values,
-}
-
-#[derive(Debug)]
-pub struct Blueprint {
+/// Robot factory with `N` resource types, robot `i` collects resource `i`.
+///
+/// The last resource is the one to maximize, the puzzle uses ore, clay, obsidian and geode.
+#[derive(Debug, Clone)]
+pub struct Blueprint<const N: usize> {
     id: u8,
-    ore_robot_cost: Values,
-    clay_robot_cost: Values,
-    obsidian_robot_cost: Values,
-    geode_robot_cost: Values,
+    names: [String; N],
+    /// `costs[robot][resource]`
+    costs: [Resources<N>; N],
 }
 
-const ZERO: Values = Values {
-    ore: 0,
-    clay: 0,
-    obsidian: 0,
-    geode: 0,
-};
-const ONE_ORE: Values = Values { ore: 1, ..ZERO };
-
-impl std::fmt::Debug for Step {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "Robots=> Ore: {:2}, Clay: {:2}, Obsidian: {:2}, Geode: {:2}",
-            self.robots.ore, self.robots.clay, self.robots.obsidian, self.robots.geode
-        ))
-        .unwrap();
-
-        f.write_str("  |  ").unwrap();
-        f.write_fmt(format_args!(
-            "Res=> Ore: {:2}, Clay: {:2}, Obsidian: {:2}, Geode: {:2}",
-            self.resources.ore, self.resources.clay, self.resources.obsidian, self.resources.geode
-        ))
-    }
-}
+/// Robot kind and its cost as written in the blueprint
+type RobotSpec<'a> = (&'a str, Vec<(Number, &'a str)>);
 
-impl Values {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        let (input, ore) = opt(terminated(u8, tuple((space1, tag("ore")))))(input)?;
-        let (input, clay) = opt(preceded(ws(tag("and")), terminated(u8, tuple((space1, tag("clay"))))))(input)?;
-        let (input, obsidian) = opt(preceded(
-            ws(tag("and")),
-            terminated(u8, tuple((space1, tag("obsidian")))),
-        ))(input)?;
-
-        Ok((
-            input,
-            Values {
-                ore: ore.unwrap_or(0_u8),
-                clay: clay.unwrap_or(0_u8),
-                obsidian: obsidian.unwrap_or(0_u8),
-                geode: 0,
-            },
-        ))
+impl<const N: usize> Blueprint<N> {
+    fn parse_robot(input: &str) -> IResult<&str, RobotSpec<'_>> {
+        tuple((
+            delimited(tag("Each "), alpha1, tag(" robot costs ")),
+            terminated(
+                separated_list1(ws(tag("and")), separated_pair(u16, space1, alpha1)),
+                tag("."),
+            ),
+        ))(input)
     }
 
-    fn try_build(&self, resources: &Values) -> Option<Values> {
-        if self.ore <= resources.ore && self.clay <= resources.clay && self.obsidian <= resources.obsidian {
-            Some(*resources - *self)
-        } else {
-            None
-        }
+    fn parse(input: &str) -> IResult<&str, (u8, Vec<RobotSpec<'_>>)> {
+        tuple((
+            delimited(tuple((tag("Blueprint"), multispace1)), u8, tag(":")),
+            many1(preceded(multispace1, Self::parse_robot)),
+        ))(input)
     }
-}
 
-impl Sub for Values {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            ore: self.ore - rhs.ore,
-            clay: self.clay - rhs.clay,
-            obsidian: self.obsidian - rhs.obsidian,
-            geode: self.geode - rhs.geode,
+    /// Robots are numbered in the order they are listed, costs may refer to any of them
+    fn from_specs(id: u8, robots: &[RobotSpec]) -> Result<Self, String> {
+        if robots.len() != N {
+            return Err(format!("blueprint {id} has {} robots instead of {N}", robots.len()));
         }
-    }
-}
 
-impl Add for Values {
-    type Output = Self;
+        let names: [String; N] = std::array::from_fn(|idx| robots[idx].0.to_string());
+        let mut costs = [[0; N]; N];
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            ore: self.ore + rhs.ore,
-            clay: self.clay + rhs.clay,
-            obsidian: self.obsidian + rhs.obsidian,
-            geode: self.geode + rhs.geode,
+        for (robot, (_, cost)) in robots.iter().enumerate() {
+            for &(amount, resource) in cost {
+                let resource = names
+                    .iter()
+                    .position(|name| name == resource)
+                    .ok_or_else(|| format!("blueprint {id}: no robot collects {resource}"))?;
+                costs[robot][resource] += amount;
+            }
         }
-    }
-}
 
-impl AddAssign for Values {
-    fn add_assign(&mut self, rhs: Self) {
-        *self = *self + rhs;
+        Ok(Blueprint { id, names, costs })
     }
 }
 
-impl Blueprint {
-    fn parse(input: &str) -> IResult<&str, Self> {
-        let (input, id) = terminated(preceded(tuple((tag("Blueprint"), multispace1)), u8), tag(":"))(input)?;
+pub fn parse_input(input: &str) -> ParseResult {
+    let (_, blueprints) = many1(preceded(multispace0, Blueprint::<4>::parse))(input).unwrap();
 
-        let (input, ore_robot_cost) = preceded(
-            multispace1,
-            delimited(
-                tuple((tag("Each ore robot costs"), multispace1)),
-                Values::parse,
-                tag("."),
-            ),
-        )(input)?;
-
-        let (input, clay_robot_cost) = preceded(
-            multispace1,
-            delimited(tuple((tag("Each clay robot costs"), space1)), Values::parse, tag(".")),
-        )(input)?;
-
-        let (input, obsidian_robot_cost) = preceded(
-            multispace1,
-            delimited(
-                tuple((tag("Each obsidian robot costs"), space1)),
-                Values::parse,
-                tag("."),
-            ),
-        )(input)?;
-
-        let (input, geode_robot_cost) = preceded(
-            multispace1,
-            delimited(tuple((tag("Each geode robot costs"), space1)), Values::parse, tag(".")),
-        )(input)?;
-
-        Ok((
-            input,
-            Blueprint {
-                id,
-                ore_robot_cost,
-                clay_robot_cost,
-                obsidian_robot_cost,
-                geode_robot_cost,
-            },
-        ))
-    }
+    blueprints
+        .iter()
+        .map(|(id, robots)| Blueprint::from_specs(*id, robots).unwrap())
+        .collect()
 }
 
-pub fn parse_input(input: &str) -> ParseResult {
-    separated_list1(many1(tuple((line_ending, space0))), Blueprint::parse)(input)
-        .map(|(_, result)| result)
-        .unwrap()
+/// A robot which is built during `minute`, it starts collecting in the next minute
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Build {
+    minute: Number,
+    robot: usize,
 }
 
-pub fn part1(input: &ParseResult) -> usize {
-    input
-        .par_iter()
-        .map(find_max_number_of_geodes)
-        .zip(input)
-        .max_by_key(|(res, _)| *res)
-        .map(|(max, blueprint)| blueprint.id as usize * max)
-        .unwrap()
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SearchStats {
+    /// States which were expanded
+    pub visited: usize,
+    /// Subtrees skipped, because even building the best robot every minute cannot beat the best plan
+    pub pruned_by_bound: usize,
+    /// Robots not built, because there are already enough of them to pay for any robot every minute
+    pub pruned_by_cap: usize,
 }
 
-fn find_max_number_of_geodes(blueprint: &Blueprint) -> usize {
-    let mut paths = vec![Step {
-        resources: ZERO,
-        robots: ONE_ORE,
-    }];
-
-    for minute in 1..=24 {
-        // Determine which robot to build
-        let mut next_step = vec![];
-
-        while let Some(path) = paths.pop() {
-            // Try build a geode robot first if it is possible
-            if let Some(resources) = blueprint.geode_robot_cost.try_build(&path.resources) {
-                next_step.push(Step {
-                    resources: resources + path.robots,
-                    robots: Values {
-                        geode: path.robots.geode + 1,
-                        ..path.robots
-                    },
-                });
-                continue;
-            }
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub best: Number,
+    pub plan: Vec<Build>,
+    pub stats: SearchStats,
+}
 
-            if let Some(resources) = blueprint.obsidian_robot_cost.try_build(&path.resources) {
-                next_step.push(Step {
-                    resources: resources + path.robots,
-                    robots: Values {
-                        obsidian: path.robots.obsidian + 1,
-                        ..path.robots
-                    },
-                });
-            }
+#[derive(Copy, Clone)]
+struct State<const N: usize> {
+    elapsed: Number,
+    resources: Resources<N>,
+    robots: Resources<N>,
+}
 
-            if let Some(resources) = blueprint.clay_robot_cost.try_build(&path.resources) {
-                next_step.push(Step {
-                    resources: resources + path.robots,
-                    robots: Values {
-                        clay: path.robots.clay + 1,
-                        ..path.robots
-                    },
-                });
+impl<const N: usize> Blueprint<N> {
+    /// Plan with the most collected resources of the last kind after `minutes`, starting with one robot of the first kind
+    pub fn optimize(&self, minutes: Number) -> Search {
+        let mut robots = [0; N];
+        robots[0] = 1;
+
+        // Spending more than this per minute is impossible, so more robots do not help
+        let max_useful = std::array::from_fn(|resource| {
+            if resource == N - 1 {
+                Number::MAX
+            } else {
+                self.costs.iter().map(|cost| cost[resource]).max().unwrap_or(0)
             }
+        });
 
-            if let Some(resources) = blueprint.ore_robot_cost.try_build(&path.resources) {
-                next_step.push(Step {
-                    resources: resources + path.robots,
-                    robots: Values {
-                        ore: path.robots.ore + 1,
-                        ..path.robots
-                    },
-                });
-            }
+        let mut search = Search {
+            best: 0,
+            plan: vec![],
+            stats: SearchStats::default(),
+        };
+        let start = State {
+            elapsed: 0,
+            resources: [0; N],
+            robots,
+        };
+        self.branch(start, minutes, &max_useful, &mut vec![], &mut search);
+
+        search
+    }
 
-            // Robots working only producing more resources
-            next_step.push(Step {
-                resources: path.resources + path.robots,
-                robots: path.robots,
-            });
+    fn branch(
+        &self,
+        state: State<N>,
+        minutes: Number,
+        max_useful: &Resources<N>,
+        path: &mut Vec<Build>,
+        search: &mut Search,
+    ) {
+        search.stats.visited += 1;
+        let remaining = minutes - state.elapsed;
+
+        // Just wait for the robots to finish collecting
+        let collected = state.resources[N - 1] + state.robots[N - 1] * remaining;
+        if collected > search.best {
+            search.best = collected;
+            search.plan = path.clone();
         }
 
-        paths = next_step;
+        if collected + remaining * remaining.saturating_sub(1) / 2 <= search.best {
+            search.stats.pruned_by_bound += 1;
+            return;
+        }
 
-        // Do agressive pruning of path which are not worth following
-        let mut pruned: Vec<Step> = Vec::with_capacity(paths.len());
+        // Try the most valuable robots first, so good plans are found early and prune more
+        for robot in (0..N).rev() {
+            if state.robots[robot] >= max_useful[robot] {
+                search.stats.pruned_by_cap += 1;
+                continue;
+            }
 
-        paths.sort_unstable_by(|a, b| {
-            a.robots
-                .geode
-                .cmp(&b.robots.geode)
-                .then(a.robots.clay.cmp(&b.robots.clay))
-                .then(a.robots.obsidian.cmp(&b.robots.obsidian))
-                .then(a.robots.geode.cmp(&b.robots.geode))
-        });
+            let Some(wait) = self.minutes_until_affordable(&state, robot) else {
+                continue;
+            };
 
-        while let Some(path) = paths.pop() {
-            if pruned.iter().any(|s| s.robots.geode > path.robots.geode) {
-                // Skip
+            // A robot finished in the last minute does not collect anything anymore
+            let elapsed = state.elapsed + wait + 1;
+            if elapsed >= minutes {
                 continue;
             }
 
-            if pruned.iter().any(|s| s.robots.obsidian > path.robots.obsidian) {
-                // Skip
-                //continue;
-            }
+            let mut next = State {
+                elapsed,
+                resources: std::array::from_fn(|idx| {
+                    state.resources[idx] + state.robots[idx] * (wait + 1) - self.costs[robot][idx]
+                }),
+                robots: state.robots,
+            };
+            next.robots[robot] += 1;
+
+            path.push(Build { minute: elapsed, robot });
+            self.branch(next, minutes, max_useful, path, search);
+            path.pop();
+        }
+    }
 
-            if pruned.iter().any(|s| s.robots.clay > path.robots.clay) {
-                // Skip
-                //continue;
-            }
+    /// Minutes to collect the resources for `robot`, `None` if nothing collects a required resource
+    fn minutes_until_affordable(&self, state: &State<N>, robot: usize) -> Option<Number> {
+        (0..N)
+            .map(|idx| {
+                let missing = self.costs[robot][idx].saturating_sub(state.resources[idx]);
+                match (missing, state.robots[idx]) {
+                    (0, _) => Some(0),
+                    (_, 0) => None,
+                    (missing, robots) => Some(missing.div_ceil(robots)),
+                }
+            })
+            .try_fold(0, |wait, minutes| Some(wait.max(minutes?)))
+    }
 
-            if pruned.iter().any(|s| s.robots.ore > path.robots.ore) {
-                // Skip
-                //continue;
+    /// Replay a plan minute by minute, returns the resources at the end or `None` if a build is not affordable
+    pub fn simulate(&self, minutes: Number, plan: &[Build]) -> Option<Resources<N>> {
+        let mut resources: Resources<N> = [0; N];
+        let mut robots = [0; N];
+        robots[0] = 1;
+        let mut builds = plan.iter().peekable();
+
+        for minute in 1..=minutes {
+            let build = builds.next_if(|build| build.minute == minute);
+            if let Some(build) = build {
+                for (resource, cost) in resources.iter_mut().zip(&self.costs[build.robot]) {
+                    *resource = resource.checked_sub(*cost)?;
+                }
             }
 
-            if pruned
-                .iter()
-                .any(|s| s.robots == path.robots && s.resources == path.resources)
-            {
-                // Skip
-                continue;
+            for (resource, robots) in resources.iter_mut().zip(&robots) {
+                *resource += robots;
             }
 
-            pruned.push(path);
+            if let Some(build) = build {
+                robots[build.robot] += 1;
+            }
         }
 
-        paths = pruned;
+        // Builds after the time limit or out of order
+        builds.next().is_none().then_some(resources)
+    }
 
-        dbg!(minute);
-        dbg!(paths.len());
-        //dbg!(&paths);
-        println!();
+    pub fn describe(&self, plan: &[Build]) -> String {
+        plan.iter()
+            .map(|build| format!("minute {:2}: {} robot", build.minute, self.names[build.robot]))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
+}
 
-    //dbg!(&paths);
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "visited {}, pruned by bound {}, pruned by robot cap {}",
+            self.visited, self.pruned_by_bound, self.pruned_by_cap
+        )
+    }
+}
 
-    paths.iter().map(|s| s.resources.geode).max().unwrap() as usize
+pub fn part1(input: &ParseResult) -> usize {
+    input
+        .par_iter()
+        .map(|blueprint| blueprint.id as usize * blueprint.optimize(24).best as usize)
+        .sum()
 }
 
-pub fn part2(input: &ParseResult) -> isize {
-    42
+pub fn part2(input: &ParseResult) -> usize {
+    input
+        .par_iter()
+        .take(3)
+        .map(|blueprint| blueprint.optimize(32).best as usize)
+        .product()
 }
 
 #[cfg(test)]
@@ -318,24 +280,84 @@ mod tests {
     #[test]
     fn example_part1() {
         let input = parse_input(EXAMPLE);
-        assert_eq!(part1(&input), 42);
+        assert_eq!(part1(&input), 33);
     }
 
     #[test]
     fn example_part2() {
         let input = parse_input(EXAMPLE);
-        assert_eq!(part2(&input), 42);
+        assert_eq!(part2(&input), 56 * 62);
     }
 
     #[test]
     fn input_part1() {
         let input = parse_input(INPUT);
-        assert_eq!(part1(&input), 42);
+        assert_eq!(part1(&input), 1981);
     }
 
     #[test]
     fn input_part2() {
         let input = parse_input(INPUT);
-        assert_eq!(part2(&input), 42);
+        assert_eq!(part2(&input), 10962);
+    }
+
+    /// Every possible build in every minute, only feasible for short time limits
+    fn brute_force<const N: usize>(blueprint: &Blueprint<N>, minutes: Number, plan: &mut Vec<Build>) -> Number {
+        let resources = blueprint.simulate(minutes, plan).unwrap();
+        let mut best = resources[N - 1];
+
+        let first_minute = plan.last().map_or(1, |build| build.minute + 1);
+        for minute in first_minute..minutes {
+            for robot in 0..N {
+                plan.push(Build { minute, robot });
+                if blueprint.simulate(minutes, plan).is_some() {
+                    best = best.max(brute_force(blueprint, minutes, plan));
+                }
+                plan.pop();
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn build_plan() {
+        let blueprints = parse_input(EXAMPLE);
+
+        for (blueprint, minutes, geodes) in [
+            (&blueprints[0], 24, 9),
+            (&blueprints[1], 24, 12),
+            (&blueprints[0], 32, 56),
+        ] {
+            let search = blueprint.optimize(minutes);
+            assert_eq!(search.best, geodes);
+            assert_eq!(blueprint.simulate(minutes, &search.plan).unwrap()[3], geodes);
+            assert!(search.stats.pruned_by_bound > 0 && search.stats.pruned_by_cap > 0);
+        }
+
+        let plan = [Build { minute: 3, robot: 1 }, Build { minute: 5, robot: 1 }];
+        assert_eq!(
+            blueprints[0].describe(&plan),
+            "minute  3: clay robot\nminute  5: clay robot"
+        );
+        assert_eq!(blueprints[0].simulate(5, &plan), Some([1, 2, 0, 0]));
+        assert_eq!(blueprints[0].simulate(5, &[Build { minute: 2, robot: 0 }]), None);
+        assert_eq!(blueprints[0].simulate(4, &plan), None);
+    }
+
+    #[test]
+    fn other_resources() {
+        let input = "Blueprint 7: Each wood robot costs 2 wood. Each stone robot costs 3 wood. \
+                     Each gold robot costs 1 wood and 4 stone.";
+        let (_, (id, robots)) = Blueprint::<3>::parse(input).unwrap();
+        let blueprint = Blueprint::<3>::from_specs(id, &robots).unwrap();
+        assert_eq!(blueprint.costs, [[2, 0, 0], [3, 0, 0], [1, 4, 0]]);
+        assert!(Blueprint::<4>::from_specs(id, &robots).is_err());
+
+        for minutes in [8, 10, 12] {
+            let search = blueprint.optimize(minutes);
+            assert_eq!(search.best, brute_force(&blueprint, minutes, &mut vec![]));
+            assert_eq!(blueprint.simulate(minutes, &search.plan).unwrap()[2], search.best);
+        }
     }
 }
@@ -1,7 +1,6 @@
 use std::{
-    borrow::Cow,
-    collections::{HashMap, VecDeque},
-    fmt::{Debug, Formatter},
+    collections::HashMap,
+    fmt::{self, Debug, Display, Formatter},
 };
 
 use nom::{
@@ -13,10 +12,12 @@ use nom::{
     sequence::{separated_pair, tuple},
     IResult,
 };
+use num::{rational::Ratio, Zero};
 
 use crate::utils::ws;
 
 type Number = u64;
+type Rational = Ratio<i128>;
 type ParseResult<'a> = HashMap<&'a str, MonkeyEquation<'a>>;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -28,36 +29,17 @@ pub enum Op {
     Eq,
 }
 
-#[derive(Clone)]
-pub enum Variable<'a> {
-    Number(Number),
-    MonkeyRef(&'a str),
-}
-
 #[derive(Clone)]
 pub enum MonkeyEquation<'a> {
     Number(Number),
-    Operation {
-        op: Op,
-        left: Variable<'a>,
-        right: Variable<'a>,
-    },
-}
-
-impl Debug for Variable<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Variable::Number(nb) => write!(f, "{}", nb),
-            Variable::MonkeyRef(s) => write!(f, "{}", s),
-        }
-    }
+    Operation { op: Op, left: &'a str, right: &'a str },
 }
 
 impl Debug for MonkeyEquation<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Number(arg0) => write!(f, "{}", arg0),
-            Self::Operation { op, left, right } => write!(f, "{:?} {:?} {:?}", left, op, right),
+            Self::Operation { op, left, right } => write!(f, "{} {:?} {}", left, op, right),
         }
     }
 }
@@ -74,26 +56,13 @@ impl Debug for Op {
     }
 }
 
-impl Variable<'_> {
-    fn resolve_if_direct_ref(&self, map: &HashMap<&str, MonkeyEquation>) -> Cow<Variable> {
-        match self {
-            Variable::Number(_) => Cow::Borrowed(self),
-            Variable::MonkeyRef(monkey) => map
-                .get(monkey)
-                .and_then(|eq| eq.eval())
-                .map(|nb| Cow::Owned(Variable::Number(nb)))
-                .unwrap_or(Cow::Borrowed(self)),
-        }
-    }
-}
-
 impl<'a> MonkeyEquation<'a> {
     fn parse(input: &'a str) -> IResult<&str, Self> {
         alt((
             map(nom::character::complete::u64, MonkeyEquation::Number),
             map(
                 tuple((
-                    map(alpha1, Variable::MonkeyRef),
+                    alpha1,
                     map(ws(one_of("+-*/")), |symbol| match symbol {
                         '+' => Op::Add,
                         '-' => Op::Sub,
@@ -102,7 +71,7 @@ impl<'a> MonkeyEquation<'a> {
                         '=' => Op::Eq,
                         _ => unreachable!(),
                     }),
-                    map(alpha1, Variable::MonkeyRef),
+                    alpha1,
                 )),
                 |res| MonkeyEquation::Operation {
                     left: res.0,
@@ -112,64 +81,6 @@ impl<'a> MonkeyEquation<'a> {
             ),
         ))(input)
     }
-
-    fn eval(&self) -> Option<Number> {
-        match self {
-            MonkeyEquation::Number(nb) => Some(*nb),
-            MonkeyEquation::Operation { op, left, right } => match (left, op, right) {
-                (Variable::Number(nb1), Op::Add, Variable::Number(nb2)) => Some(nb1 + nb2),
-                (Variable::Number(nb1), Op::Sub, Variable::Number(nb2)) => Some(nb1 - nb2),
-                (Variable::Number(nb1), Op::Mult, Variable::Number(nb2)) => Some(nb1 * nb2),
-                (Variable::Number(nb1), Op::Div, Variable::Number(nb2)) => Some(nb1 / nb2),
-                _ => None,
-            },
-        }
-    }
-
-    fn rearrange(&self, nb: Number, map: &HashMap<&str, MonkeyEquation>) -> Option<(String, Number)> {
-        match self {
-            MonkeyEquation::Number(_) => None,
-            MonkeyEquation::Operation { op, left, right } => {
-                let left_cow = left.resolve_if_direct_ref(map);
-                let left = left_cow.as_ref();
-
-                let right_cow = right.resolve_if_direct_ref(map);
-                let right = right_cow.as_ref();
-
-                match (left, op, right) {
-                    (Variable::Number(nb1), Op::Add, Variable::MonkeyRef(monkey)) => {
-                        Some((monkey.to_string(), nb - nb1))
-                    }
-                    (Variable::MonkeyRef(monkey), Op::Add, Variable::Number(nb1)) => {
-                        Some((monkey.to_string(), nb - nb1))
-                    }
-
-                    (Variable::Number(nb1), Op::Sub, Variable::MonkeyRef(monkey)) => {
-                        Some((monkey.to_string(), nb1 - nb))
-                    }
-                    (Variable::MonkeyRef(monkey), Op::Sub, Variable::Number(nb1)) => {
-                        Some((monkey.to_string(), nb + nb1))
-                    }
-
-                    (Variable::Number(nb1), Op::Mult, Variable::MonkeyRef(monkey)) => {
-                        Some((monkey.to_string(), nb / nb1))
-                    }
-                    (Variable::MonkeyRef(monkey), Op::Mult, Variable::Number(nb1)) => {
-                        Some((monkey.to_string(), nb / nb1))
-                    }
-
-                    (Variable::Number(nb1), Op::Div, Variable::MonkeyRef(monkey)) => {
-                        Some((monkey.to_string(), nb1 / nb))
-                    }
-                    (Variable::MonkeyRef(monkey), Op::Div, Variable::Number(nb1)) => {
-                        Some((monkey.to_string(), nb * nb1))
-                    }
-
-                    _ => None,
-                }
-            }
-        }
-    }
 }
 
 pub fn parse_input(input: &str) -> ParseResult {
@@ -180,204 +91,185 @@ pub fn parse_input(input: &str) -> ParseResult {
         .collect()
 }
 
-pub fn part1(input: &ParseResult) -> Number {
-    let mut equations = input.clone();
-
-    let mut work_queue: VecDeque<&str> = VecDeque::new();
-    work_queue.push_back("root");
-
-    while let Some(monkey_to_solve) = work_queue.pop_back() {
-        let Some((op, nb1, nb2)) = (match &equations[monkey_to_solve] {
-            MonkeyEquation::Number(_) => { None },
-            MonkeyEquation::Operation{op, left, right } => {
-                let nb_left = match left {
-                    Variable::Number(_) => left.clone(),
-                    Variable::MonkeyRef(monkey) => {
-                        if let MonkeyEquation::Number(nb) = equations[monkey] {
-                            Variable::Number(nb)
-                        } else {
-                            work_queue.push_back(monkey_to_solve);
-                            work_queue.push_back(monkey);
-                            Variable::MonkeyRef(monkey)
-                        }
-                    }
-                };
-
-                let nb_right = match right {
-                    Variable::Number(_) => right.clone(),
-                    Variable::MonkeyRef(monkey) => {
-                        if let MonkeyEquation::Number(nb) = equations[monkey] {
-                            Variable::Number(nb)
-                        } else {
-                            work_queue.push_back(monkey_to_solve);
-                            work_queue.push_back(monkey);
-                            Variable::MonkeyRef(monkey)
-                        }
-                    }
-                };
+/// Expression tree of a monkey, with the monkeys it depends on inlined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Const(Rational),
+    /// The monkey to solve for
+    Unknown(String),
+    Binary { op: Op, left: Box<Expr>, right: Box<Expr> },
+}
 
-                Some((op, nb_left, nb_right))
-            },
-        })
-        else {
-            continue;
-        };
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    UnknownMonkey(String),
+    DivisionByZero,
+    /// The unknown is not part of the equation
+    NotFound,
+    /// The unknown appears on both sides of an operation, which cannot be inverted
+    NotLinear,
+    NotAnInteger(Rational),
+    /// Monkeys only use integers, so every division has to be exact
+    InexactDivision(Expr),
+}
 
-        match (&nb1, &nb2) {
-            (Variable::Number(nb1), Variable::Number(nb2)) => {
-                equations.insert(
-                    monkey_to_solve,
-                    MonkeyEquation::Number(match op {
-                        Op::Add => nb1 + nb2,
-                        Op::Sub => nb1 - nb2,
-                        Op::Mult => nb1 * nb2,
-                        Op::Div => nb1 / nb2,
-                        Op::Eq => unreachable!(),
-                    }),
-                );
-            }
-            _ => {
-                equations.insert(
-                    monkey_to_solve,
-                    MonkeyEquation::Operation {
-                        op: *op,
-                        left: nb1.clone(),
-                        right: nb2.clone(),
-                    },
-                );
-            }
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::UnknownMonkey(monkey) => write!(f, "monkey {monkey} does not exist"),
+            SolveError::DivisionByZero => write!(f, "division by zero"),
+            SolveError::NotFound => write!(f, "the unknown is not part of the equation"),
+            SolveError::NotLinear => write!(f, "the unknown appears on both sides of an operation"),
+            SolveError::NotAnInteger(value) => write!(f, "{value} is not an integer"),
+            SolveError::InexactDivision(expr) => write!(f, "{expr} is not an integer"),
         }
     }
-
-    let MonkeyEquation::Number(result) = equations["root"] else {
-        panic!("Algorithm not working");
-    };
-    result
 }
 
-pub fn part2(input: &ParseResult) -> Number {
-    let mut equations = input.clone();
-    let mut already_solved = HashMap::new();
-
-    if let MonkeyEquation::Operation { op: _, left, right } = equations.remove("root").unwrap() {
-        equations.insert(
-            "root",
-            MonkeyEquation::Operation {
-                op: Op::Eq,
-                left,
-                right,
-            },
-        );
-    }
-
-    let mut work_queue: VecDeque<&str> = VecDeque::new();
-    work_queue.push_back("root");
+impl std::error::Error for SolveError {}
 
-    equations.insert(
-        "humn",
-        MonkeyEquation::Operation {
-            op: Op::Add,
-            left: Variable::Number(0),
-            right: Variable::MonkeyRef("finished"),
-        },
-    );
-
-    while let Some(monkey_to_solve) = work_queue.pop_back() {
-        if let Some(nb) = already_solved.get_mut(monkey_to_solve) {
-            if *nb == 3 {
-                continue;
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(value) => write!(f, "{value}"),
+            Expr::Unknown(monkey) => write!(f, "{monkey}"),
+            Expr::Binary { op, left, right } => {
+                let operand = |expr: &Expr| match expr {
+                    Expr::Binary { .. } => format!("({expr})"),
+                    _ => expr.to_string(),
+                };
+                write!(f, "{} {op:?} {}", operand(left), operand(right))
             }
+        }
+    }
+}
 
-            *nb += 1;
-        } else {
-            already_solved.insert(monkey_to_solve, 1);
+fn apply(op: Op, left: Rational, right: Rational) -> Result<Rational, SolveError> {
+    Ok(match op {
+        Op::Add => left + right,
+        Op::Sub => left - right,
+        Op::Mult => left * right,
+        Op::Div if right.is_zero() => return Err(SolveError::DivisionByZero),
+        Op::Div => left / right,
+        Op::Eq => unreachable!("only the root compares"),
+    })
+}
+
+impl Expr {
+    /// Build the expression of `monkey`, `unknown` is kept as a variable instead of its number
+    pub fn build(equations: &ParseResult, monkey: &str, unknown: Option<&str>) -> Result<Expr, SolveError> {
+        if Some(monkey) == unknown {
+            return Ok(Expr::Unknown(monkey.to_string()));
         }
 
-        let Some(solved_eq) = (match &equations[monkey_to_solve] {
-            MonkeyEquation::Number(_) => { None },
-            MonkeyEquation::Operation{op, left, right } => {
-                let nb_left = match left {
-                    Variable::Number(_) => left.clone(),
-                    Variable::MonkeyRef(monkey) => {
-                        if *monkey == "humn" {
-                            Variable::MonkeyRef(monkey)
-                        }
-                        else if let MonkeyEquation::Number(nb) = equations[monkey] {
-                            Variable::Number(nb)
-                        } else {
-                            work_queue.push_back(monkey_to_solve);
-                            work_queue.push_back(monkey);
-                            Variable::MonkeyRef(monkey)
-                        }
-                    }
-                };
+        let operand = |monkey: &str| Expr::build(equations, monkey, unknown);
 
-                let nb_right = match right {
-                    Variable::Number(_) => right.clone(),
-                    Variable::MonkeyRef(monkey) => {
-                        if *monkey == "humn" {
-                            Variable::MonkeyRef(monkey)
-                        }
-                        else if let MonkeyEquation::Number(nb) = equations[monkey] {
-                            Variable::Number(nb)
-                        } else {
-                            work_queue.push_back(monkey_to_solve);
-                            work_queue.push_back(monkey);
-                            Variable::MonkeyRef(monkey)
-                        }
-                    }
-                };
+        match equations.get(monkey) {
+            None => Err(SolveError::UnknownMonkey(monkey.to_string())),
+            Some(MonkeyEquation::Number(nb)) => Ok(Expr::Const(Rational::from(*nb as i128))),
+            Some(MonkeyEquation::Operation { op, left, right }) => Ok(Expr::Binary {
+                op: *op,
+                left: Box::new(operand(left)?),
+                right: Box::new(operand(right)?),
+            }),
+        }
+    }
 
-                Some(MonkeyEquation::Operation {
-                    op: *op,
-                    left: nb_left,
-                    right: nb_right,
-                })
-            },
-        })
-        else {
-            continue;
+    /// Replace all subtrees without the unknown by their value
+    pub fn simplify(self) -> Result<Expr, SolveError> {
+        let Expr::Binary { op, left, right } = self else {
+            return Ok(self);
         };
 
-        if let Some(result) = solved_eq.eval() {
-            equations.insert(monkey_to_solve, MonkeyEquation::Number(result));
-        } else {
-            equations.insert(monkey_to_solve, solved_eq);
+        match (left.simplify()?, right.simplify()?) {
+            (Expr::Const(left), Expr::Const(right)) if op != Op::Eq => {
+                let value = apply(op, left, right)?;
+                if !value.is_integer() {
+                    return Err(SolveError::InexactDivision(Expr::Binary {
+                        op,
+                        left: Box::new(Expr::Const(left)),
+                        right: Box::new(Expr::Const(right)),
+                    }));
+                }
+                Ok(Expr::Const(value))
+            }
+            (left, right) => Ok(Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            }),
         }
     }
 
-    //dbg!(&equations);
-
-    // reverse the root equation
-    let (rev_root, mut value) = if let MonkeyEquation::Operation { op: _, left, right } = equations.get("root").unwrap()
-    {
-        match (left, right) {
-            (Variable::MonkeyRef(monkey), Variable::Number(nb)) => (*monkey, *nb),
-            (Variable::Number(nb), Variable::MonkeyRef(monkey)) => (*monkey, *nb),
-            _ => panic!(),
+    /// The value of the unknown for which the simplified expression equals `value`.
+    ///
+    /// Every operation on the path to the unknown is inverted, the other operand has to be a constant.
+    /// An `Eq` ignores `value` and solves for the constant side instead.
+    pub fn solve(&self, value: Rational) -> Result<Rational, SolveError> {
+        match self {
+            Expr::Const(_) => Err(SolveError::NotFound),
+            Expr::Unknown(_) => Ok(value),
+            Expr::Binary { op, left, right } => match (left.as_ref(), right.as_ref()) {
+                (Expr::Const(_), Expr::Const(_)) => Err(SolveError::NotFound),
+                (expr, Expr::Const(constant)) => expr.solve(match op {
+                    Op::Add => apply(Op::Sub, value, *constant)?,
+                    Op::Sub => apply(Op::Add, value, *constant)?,
+                    Op::Mult => apply(Op::Div, value, *constant)?,
+                    Op::Div => apply(Op::Mult, value, *constant)?,
+                    Op::Eq => *constant,
+                }),
+                (Expr::Const(constant), expr) => expr.solve(match op {
+                    Op::Add => apply(Op::Sub, value, *constant)?,
+                    Op::Mult => apply(Op::Div, value, *constant)?,
+                    Op::Sub | Op::Div => apply(*op, *constant, value)?,
+                    Op::Eq => *constant,
+                }),
+                _ => Err(SolveError::NotLinear),
+            },
         }
-    } else {
-        panic!()
+    }
+}
+
+/// Value of `unknown` which makes both sides of the root equal
+pub fn solve_for(equations: &ParseResult, unknown: &str) -> Result<Rational, SolveError> {
+    let Some(MonkeyEquation::Operation { left, right, .. }) = equations.get("root") else {
+        return Err(SolveError::UnknownMonkey(String::from("root")));
     };
 
-    let mut rev_root = rev_root.to_string();
+    let mut equations = equations.clone();
+    equations.insert(
+        "root",
+        MonkeyEquation::Operation {
+            op: Op::Eq,
+            left,
+            right,
+        },
+    );
 
-    loop {
-        let Some((rev_root_new, value_new)) = equations.get(rev_root.as_str()).unwrap().rearrange(value, &equations) else {
-            dbg!(equations.get(rev_root.as_str()));
-            panic!("Algorithm not working");
-        };
+    Expr::build(&equations, "root", Some(unknown))?
+        .simplify()?
+        .solve(Rational::zero())
+}
 
-        if rev_root_new == "humn" {
-            return value;
-        }
+fn to_number(value: Rational) -> Result<Number, SolveError> {
+    if value.is_integer() {
+        Number::try_from(value.to_integer()).map_err(|_| SolveError::NotAnInteger(value))
+    } else {
+        Err(SolveError::NotAnInteger(value))
+    }
+}
 
-        rev_root = rev_root_new.to_string();
-        value = value_new;
-        //dbg!(&rev_root, &value);
+pub fn part1(input: &ParseResult) -> Number {
+    match Expr::build(input, "root", None).and_then(Expr::simplify) {
+        Ok(Expr::Const(value)) => to_number(value).unwrap(),
+        result => panic!("root could not be evaluated: {result:?}"),
     }
 }
 
+pub fn part2(input: &ParseResult) -> Number {
+    solve_for(input, "humn").and_then(to_number).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -410,61 +302,48 @@ mod tests {
     }
 
     #[test]
-    fn test_rearrange() {
-        let eq = MonkeyEquation::Operation {
-            left: Variable::MonkeyRef("x"),
-            op: Op::Add,
-            right: Variable::Number(4),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 16)));
-
-        let eq = MonkeyEquation::Operation {
-            left: Variable::Number(4),
-            op: Op::Add,
-            right: Variable::MonkeyRef("x"),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 16)));
+    fn infix_and_simplify() {
+        let input = parse_input(EXAMPLE);
 
-        let eq = MonkeyEquation::Operation {
-            left: Variable::MonkeyRef("x"),
-            op: Op::Sub,
-            right: Variable::Number(4),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 24)));
+        let expr = Expr::build(&input, "pppw", Some("humn")).unwrap();
+        assert_eq!(expr.to_string(), "(4 + (2 * (humn - 3))) / 4");
+        assert_eq!(expr.simplify().unwrap().to_string(), "(4 + (2 * (humn - 3))) / 4");
 
-        let eq = MonkeyEquation::Operation {
-            left: Variable::Number(30),
-            op: Op::Sub,
-            right: Variable::MonkeyRef("x"),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 10)));
-
-        let eq = MonkeyEquation::Operation {
-            left: Variable::MonkeyRef("x"),
-            op: Op::Mult,
-            right: Variable::Number(4),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 5)));
+        let expr = Expr::build(&input, "sjmn", Some("humn")).unwrap();
+        assert_eq!(expr.to_string(), "(32 - 2) * 5");
+        assert_eq!(expr.simplify().unwrap(), Expr::Const(Rational::from(150)));
+    }
 
-        let eq = MonkeyEquation::Operation {
-            left: Variable::Number(4),
-            op: Op::Mult,
-            right: Variable::MonkeyRef("x"),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 5)));
+    #[test]
+    fn solve_any_leaf() {
+        let input = parse_input(EXAMPLE);
 
-        let eq = MonkeyEquation::Operation {
-            left: Variable::MonkeyRef("x"),
-            op: Op::Div,
-            right: Variable::Number(4),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 80)));
+        // With humn = 5 the left side is 8 / 4 = 2 and the right side (32 - 2) * 5 = 150
+        assert_eq!(solve_for(&input, "humn"), Ok(Rational::from(301)));
+        assert_eq!(solve_for(&input, "lfqf"), Ok(Rational::new(4, 75)));
+        assert_eq!(solve_for(&input, "dbpl"), Ok(Rational::new(1, 15)));
+        assert_eq!(solve_for(&input, "hmdt"), Ok(Rational::new(12, 5)));
+        assert_eq!(solve_for(&input, "humn").and_then(to_number), Ok(301));
+        assert_eq!(
+            solve_for(&input, "lfqf").and_then(to_number),
+            Err(SolveError::NotAnInteger(Rational::new(4, 75)))
+        );
+        assert_eq!(solve_for(&input, "nobody"), Err(SolveError::NotFound));
+    }
 
-        let eq = MonkeyEquation::Operation {
-            left: Variable::Number(100),
-            op: Op::Div,
-            right: Variable::MonkeyRef("x"),
-        };
-        assert_eq!(eq.rearrange(20, &HashMap::new()), Some(("x".to_string(), 5)));
+    #[test]
+    fn inexact_division() {
+        let input = parse_input("root: half * two\nhalf: seven / two\nseven: 7\ntwo: 2");
+
+        let error = Expr::build(&input, "root", None).and_then(Expr::simplify).unwrap_err();
+        assert_eq!(error.to_string(), "7 / 2 is not an integer");
+        assert_eq!(
+            error,
+            SolveError::InexactDivision(Expr::Binary {
+                op: Op::Div,
+                left: Box::new(Expr::Const(Rational::from(7))),
+                right: Box::new(Expr::Const(Rational::from(2))),
+            })
+        );
     }
 }
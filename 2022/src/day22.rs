@@ -1,4 +1,7 @@
-use std::{collections::HashMap, ops::Add};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Add,
+};
 
 use nom::{
     branch::alt,
@@ -14,7 +17,6 @@ type Number = i32;
 #[derive(Debug)]
 pub struct InputData {
     start: Coordinate,
-    block_size: Number,
     rows: Number,
     ncol: Number,
     panel: HashMap<Coordinate, Content>,
@@ -27,7 +29,7 @@ struct Coordinate {
     y: Number,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 #[repr(u8)]
 enum Direction {
@@ -94,7 +96,6 @@ impl Content {
 
 fn parse(mut input: &str) -> IResult<&str, InputData> {
     let mut start = None;
-    let mut block_size = i32::MAX;
     let mut panel = HashMap::new();
     let mut rows: Number = 0;
     let mut ncol: Number = 0;
@@ -109,8 +110,6 @@ fn parse(mut input: &str) -> IResult<&str, InputData> {
             )),
         );
 
-        let mut cur_block_size = 0;
-
         it.enumerate().for_each(|(x, content)| {
             ncol = ncol.max(x as Number);
             rows = rows.max(y);
@@ -123,21 +122,9 @@ fn parse(mut input: &str) -> IResult<&str, InputData> {
                 start = Some(pos);
             }
 
-            if content.is_void() {
-                if cur_block_size != 0 {
-                    block_size = block_size.min(cur_block_size);
-                }
-
-                cur_block_size = 0;
-            } else {
-                cur_block_size += 1;
-            }
-
             panel.insert(pos, content);
         });
 
-        block_size = block_size.min(cur_block_size);
-
         input = (it.finish() as IResult<_, _>)?.0;
 
         // Is end of map?
@@ -164,7 +151,6 @@ fn parse(mut input: &str) -> IResult<&str, InputData> {
         "",
         InputData {
             start: start.unwrap(),
-            block_size,
             rows,
             ncol,
             panel,
@@ -182,27 +168,9 @@ pub fn part1(input: &InputData) -> isize {
 }
 
 pub fn part2(input: &InputData) -> isize {
+    let cube = Cube::fold(input).expect("the map is not a cube net");
 
-    // Create flat cube faces
-    let mut cube_coord = Vec::with_capacity(6);
-
-    for y in (0..input.rows).step_by(input.block_size as usize) {
-        for x in (0..input.ncol).step_by(input.block_size as usize) {
-            if let Some(content) = input.panel.get(&Coordinate { y, x }) {
-                if !content.is_void() {
-                    cube_coord.push((y / input.block_size, x / input.block_size, 0));
-                    print!("X");
-                    continue;
-                }
-            }
-            print!(" ");
-        }
-        println!();
-    }
-
-    dbg!(cube_coord);
-
-    follow_path(input, &skip_void_part1)
+    follow_path(input, &|position, direction, data| cube.skip_void(position, direction, data))
 }
 
 #[allow(dead_code)]
@@ -336,6 +304,155 @@ fn skip_void_part1(
     }
 }
 
+type Vector = [Number; 3];
+
+fn neg(v: Vector) -> Vector {
+    v.map(|c| -c)
+}
+
+fn dot(a: Vector, b: Vector) -> Number {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Face of the cube, with the 3D directions the map axes point to on this face
+#[derive(Debug, Copy, Clone)]
+struct Face {
+    /// Top left corner on the map
+    origin: Coordinate,
+    /// Outwards
+    normal: Vector,
+    right: Vector,
+    down: Vector,
+}
+
+impl Face {
+    fn direction(&self, direction: Direction) -> Vector {
+        match direction {
+            Direction::Right => self.right,
+            Direction::Down => self.down,
+            Direction::Left => neg(self.right),
+            Direction::Up => neg(self.down),
+        }
+    }
+
+    /// Face reached by folding over the edge in `direction`
+    fn fold(&self, direction: Direction, origin: Coordinate) -> Face {
+        let (normal, right, down) = match direction {
+            Direction::Right => (self.right, neg(self.normal), self.down),
+            Direction::Left => (neg(self.right), self.normal, self.down),
+            Direction::Down => (self.down, self.right, neg(self.normal)),
+            Direction::Up => (neg(self.down), self.right, self.normal),
+        };
+
+        Face {
+            origin,
+            normal,
+            right,
+            down,
+        }
+    }
+}
+
+/// Map folded into a cube with the edge length `size`, centered at the origin.
+///
+/// Cell centers get doubled 3D coordinates, so they stay integers: a face spans `-size..=size`.
+struct Cube {
+    size: Number,
+    faces: Vec<Face>,
+}
+
+impl Cube {
+    /// Works for any of the 11 nets, `None` if the map does not fold into a cube
+    fn fold(input: &InputData) -> Option<Cube> {
+        let cells = input.panel.values().filter(|content| !content.is_void()).count();
+        let size = (1..)
+            .map(|size| size as Number)
+            .find(|size| size * size * 6 >= cells as Number)?;
+        if (size * size * 6) as usize != cells {
+            return None;
+        }
+
+        let is_face = |origin: Coordinate| input.panel.get(&origin).is_some_and(|content| !content.is_void());
+        let first = Coordinate {
+            x: input.start.x / size * size,
+            y: input.start.y / size * size,
+        };
+
+        let mut faces = vec![Face {
+            origin: first,
+            normal: [0, 0, -1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        }];
+        let mut idx = 0;
+        while let Some(face) = faces.get(idx).copied() {
+            for direction in [Direction::Right, Direction::Down, Direction::Left, Direction::Up] {
+                let mut origin = face.origin;
+                for _ in 0..size {
+                    origin = origin + direction;
+                }
+
+                if is_face(origin) && faces.iter().all(|known| known.origin != origin) {
+                    faces.push(face.fold(direction, origin));
+                }
+            }
+            idx += 1;
+        }
+
+        let normals = faces.iter().map(|face| face.normal).collect::<HashSet<_>>();
+        (faces.len() == 6 && normals.len() == 6).then_some(Cube { size, faces })
+    }
+
+    fn face_of(&self, position: Coordinate) -> Option<&Face> {
+        self.faces.iter().find(|face| {
+            (face.origin.x..face.origin.x + self.size).contains(&position.x)
+                && (face.origin.y..face.origin.y + self.size).contains(&position.y)
+        })
+    }
+
+    /// Next cell and direction when walking over the edge of a face
+    fn wrap(&self, position: Coordinate, direction: Direction) -> (Coordinate, Direction) {
+        let face = self.face_of(position).unwrap();
+        let moving = face.direction(direction);
+
+        let col = 2 * (position.x - face.origin.x) + 1 - self.size;
+        let row = 2 * (position.y - face.origin.y) + 1 - self.size;
+        let center: Vector =
+            std::array::from_fn(|idx| face.normal[idx] * self.size + face.right[idx] * col + face.down[idx] * row);
+
+        // One half step over the edge and one half step down the side of the cube
+        let center: Vector = std::array::from_fn(|idx| center[idx] + moving[idx] - face.normal[idx]);
+
+        let next = self.faces.iter().find(|next| next.normal == moving).unwrap();
+        let new_direction = [Direction::Right, Direction::Down, Direction::Left, Direction::Up]
+            .into_iter()
+            .find(|&dir| next.direction(dir) == neg(face.normal))
+            .unwrap();
+
+        let position = Coordinate {
+            x: next.origin.x + (dot(center, next.right) + self.size - 1) / 2,
+            y: next.origin.y + (dot(center, next.down) + self.size - 1) / 2,
+        };
+        (position, new_direction)
+    }
+
+    /// Like `skip_void_part1`, but the void is crossed by walking around the cube
+    fn skip_void(
+        &self,
+        position: Coordinate,
+        direction: Direction,
+        data: &InputData,
+    ) -> Option<(Coordinate, Direction)> {
+        let from = position + direction.turn_left().turn_left();
+        let (position, direction) = self.wrap(from, direction);
+
+        match data.panel.get(&position) {
+            Some(content) if content.is_empty() => Some((position, direction)),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,6 +481,116 @@ mod tests {
     #[test]
     fn input_part2() {
         let input = parse_input(INPUT);
-        assert_eq!(part2(&input), 42);
+        assert_eq!(part2(&input), 19534);
+    }
+
+    /// All hexominoes, as sorted cell lists moved to the top left corner
+    fn hexominoes() -> HashSet<Vec<(Number, Number)>> {
+        let normalize = |mut cells: Vec<(Number, Number)>| {
+            let min_x = cells.iter().map(|c| c.0).min().unwrap();
+            let min_y = cells.iter().map(|c| c.1).min().unwrap();
+            cells.iter_mut().for_each(|c| *c = (c.0 - min_x, c.1 - min_y));
+            cells.sort_unstable();
+            cells
+        };
+
+        let mut shapes = HashSet::from([vec![(0, 0)]]);
+        for _ in 1..6 {
+            shapes = shapes
+                .iter()
+                .flat_map(|shape| {
+                    shape.iter().flat_map(move |&(x, y)| {
+                        [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                            .into_iter()
+                            .filter(|cell| !shape.contains(cell))
+                            .map(move |cell| [shape.clone(), vec![cell]].concat())
+                    })
+                })
+                .map(normalize)
+                .collect();
+        }
+
+        // Only one of all rotated and mirrored variants
+        shapes
+            .iter()
+            .filter(|shape| {
+                let variants = (0..8).map(|variant| {
+                    normalize(
+                        shape
+                            .iter()
+                            .map(|&(x, y)| {
+                                let (x, y) = if variant & 4 != 0 { (y, x) } else { (x, y) };
+                                let x = if variant & 1 != 0 { -x } else { x };
+                                let y = if variant & 2 != 0 { -y } else { y };
+                                (x, y)
+                            })
+                            .collect(),
+                    )
+                });
+                variants.min().as_ref() == Some(*shape)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn net_map(shape: &[(Number, Number)], size: Number) -> InputData {
+        let width = shape.iter().map(|c| c.0).max().unwrap() + 1;
+        let height = shape.iter().map(|c| c.1).max().unwrap() + 1;
+
+        let rows = (0..height * size)
+            .map(|y| {
+                (0..width * size)
+                    .map(|x| {
+                        if shape.contains(&(x / size, y / size)) {
+                            '.'
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        parse_input(&format!("{}\n\n1", rows.join("\n")))
+    }
+
+    #[test]
+    fn all_nets_fold() {
+        let shapes = hexominoes();
+        assert_eq!(shapes.len(), 35);
+
+        let nets = shapes
+            .iter()
+            .filter_map(|shape| Some((shape, Cube::fold(&net_map(shape, 3))?)))
+            .collect::<Vec<_>>();
+        assert_eq!(nets.len(), 11);
+
+        // Walking over any edge and straight back leads to the same cell
+        for (shape, cube) in nets {
+            let input = net_map(shape, 3);
+            for (&position, content) in &input.panel {
+                if content.is_void() {
+                    continue;
+                }
+
+                for direction in [Direction::Right, Direction::Down, Direction::Left, Direction::Up] {
+                    let next = position + direction;
+                    if input.panel.get(&next).is_some_and(|content| !content.is_void()) {
+                        continue;
+                    }
+
+                    let (wrapped, new_direction) = cube.wrap(position, direction);
+                    assert!(!input.panel[&wrapped].is_void(), "{shape:?} {position:?} {direction:?}");
+
+                    let back = new_direction.turn_left().turn_left();
+                    let (returned, returned_direction) = cube.wrap(wrapped, back);
+                    assert_eq!(
+                        (returned, returned_direction.turn_left().turn_left()),
+                        (position, direction)
+                    );
+                }
+            }
+        }
     }
 }
@@ -1,61 +1,14 @@
-use nom::{
-    branch::alt,
-    character::complete::{char, line_ending},
-    combinator::{iterator, map},
-    multi::separated_list1,
-    IResult,
-};
-use num::integer::div_mod_floor;
+use aoc_common::balanced::{Balanced, Snafu};
 
-type Number = isize;
+type Number = Balanced<Snafu>;
 type ParseResult = Vec<Number>;
 
-fn parse_snafu_number(input: &str) -> IResult<&str, Number> {
-    let mut it = iterator(
-        input,
-        alt((
-            map(char('0'), |_| 0_i8),
-            map(char('1'), |_| 1_i8),
-            map(char('2'), |_| 2_i8),
-            map(char('-'), |_| -1_i8),
-            map(char('='), |_| -2_i8),
-        )),
-    );
-
-    let digits = it.collect::<Vec<_>>(); // rev() directly on the iterator doesn't work
-    let number = digits
-        .iter()
-        .rev()
-        .enumerate()
-        .fold(0, |nb, (place, &value)| nb + (num::pow(5, place) * value as Number));
-
-    Ok((it.finish()?.0, number))
-}
-
-fn number_to_snafu(number: isize) -> String {
-    if number == 0 {
-        return "".to_string();
-    }
-
-    let (div, reminder) = div_mod_floor(number + 2, 5);
-
-    number_to_snafu(div) + match reminder {
-        0 => "=",
-        1 => "-",
-        2 => "0",
-        3 => "1",
-        4 => "2",
-        _ => unimplemented!(),
-    }
-}
-
 pub fn parse_input(input: &str) -> ParseResult {
-    separated_list1(line_ending, parse_snafu_number)(input).unwrap().1
+    input.lines().map(|line| line.trim().parse().unwrap()).collect()
 }
 
 pub fn part1(numbers: &ParseResult) -> String {
-    let sum: isize = numbers.iter().sum();
-    number_to_snafu(sum)
+    numbers.iter().sum::<Number>().to_string()
 }
 
 pub fn part2(_: &ParseResult) -> &str {
@@ -71,8 +24,9 @@ mod tests {
 
     #[test]
     fn feature() {
-        println!("{}", parse_snafu_number("1==").unwrap().1);
-        println!("{}", parse_snafu_number("1==").unwrap().1);
+        let numbers = parse_input(EXAMPLE);
+        assert_eq!(numbers[0].to_i128(), Some(1747));
+        assert_eq!("1==".parse::<Number>().unwrap().to_i128(), Some(13));
     }
 
     #[test]
@@ -84,6 +38,6 @@ mod tests {
     #[test]
     fn input_part1() {
         let input = parse_input(INPUT);
-        assert_eq!(part1(&input), "todo");
+        assert_eq!(part1(&input), "2-02===-21---2002==0");
     }
 }
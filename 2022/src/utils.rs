@@ -1,15 +1,5 @@
-use nom::{character::complete::multispace0, error::ParseError, sequence::delimited, IResult};
-
-/// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
-/// trailing whitespace, returning the output of `inner`.
-/// https://github.com/Geal/nom/blob/main/doc/nom_recipes.md
-#[allow(dead_code)]
-pub fn ws<'a, F: 'a, O, E: ParseError<&'a str>>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
-where
-    F: Fn(&'a str) -> IResult<&'a str, O, E>,
-{
-    delimited(multispace0, inner, multispace0)
-}
+#[allow(unused_imports)]
+pub use aoc_common::parse::ws;
 
 #[macro_export]
 macro_rules! regex {
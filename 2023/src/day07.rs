@@ -1,6 +1,3 @@
-use std::cmp::Ordering;
-use std::collections::HashMap;
-
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 use nom::bytes::complete::{tag, take};
@@ -17,28 +14,6 @@ type ParseResult = Vec<Deck>;
 #[rustfmt::skip]
 pub enum Card { _2, _3, _4, _5, _6, _7, _8, _9, T, J, Q, K, A }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[rustfmt::skip]
-pub enum CardJoker { J, _2, _3, _4, _5, _6, _7, _8, _9, T, Q, K, A }
-
-fn map_part1_part2(c: &Card) -> CardJoker {
-    match c {
-        Card::_2 => CardJoker::_2,
-        Card::_3 => CardJoker::_3,
-        Card::_4 => CardJoker::_4,
-        Card::_5 => CardJoker::_5,
-        Card::_6 => CardJoker::_6,
-        Card::_7 => CardJoker::_7,
-        Card::_8 => CardJoker::_8,
-        Card::_9 => CardJoker::_9,
-        Card::T => CardJoker::T,
-        Card::J => CardJoker::J,
-        Card::Q => CardJoker::Q,
-        Card::K => CardJoker::K,
-        Card::A => CardJoker::A,
-    }
-}
-
 impl std::fmt::Display for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -63,13 +38,10 @@ impl std::fmt::Display for Card {
 #[rustfmt::skip]
 pub enum DeckScore { HighCard, OnePair, TwoPair, ThreeOfAKind, FullHouse, FourOfAKind, FiveOfAKind }
 
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Deck {
     cards: [Card; 5],
     bid: u16,
-    counted: Option<HashMap<usize, Vec<Card>>>,
-    score: Option<DeckScore>,
-    part2: bool,
 }
 
 impl Card {
@@ -84,160 +56,81 @@ impl Card {
     }
 }
 
-impl DeckScore {
-    fn from_deck(deck: &Deck) -> Self {
-        let counted = deck.counted.as_ref().unwrap();
-
-        if deck.cards.iter().all_equal() {
-            DeckScore::FiveOfAKind
-        } else if counted.get(&4).is_some() {
-            DeckScore::FourOfAKind
-        } else if counted.get(&3).is_some() {
-            if counted.get(&2).is_some() {
-                DeckScore::FullHouse
-            } else {
-                DeckScore::ThreeOfAKind
-            }
-        } else if let Some(pairs) = counted.get(&2) {
-            if pairs.len() == 2 {
-                DeckScore::TwoPair
-            } else {
-                DeckScore::OnePair
-            }
-        } else {
-            DeckScore::HighCard
-        }
-    }
-
-    fn from_deck_part2(deck: &Deck) -> Self {
-        let part1 = Self::from_deck(deck);
-        let counted = deck.counted.as_ref().unwrap();
-
-        match part1 {
-            DeckScore::FourOfAKind => {
-                if counted.get(&1).unwrap()[0] == Card::J || counted.get(&4).unwrap()[0] == Card::J {
-                    return DeckScore::FiveOfAKind;
-                }
-            }
-            DeckScore::FullHouse => {
-                if counted.get(&3).unwrap()[0] == Card::J || counted.get(&2).unwrap()[0] == Card::J {
-                    return DeckScore::FiveOfAKind;
-                }
-            }
-            DeckScore::ThreeOfAKind => {
-                if deck.cards.iter().any(|c| *c == Card::J) {
-                    return DeckScore::FourOfAKind;
-                }
-            }
-            DeckScore::TwoPair => {
-                if counted.get(&1).unwrap()[0] == Card::J {
-                    return DeckScore::FullHouse;
-                } else if counted.get(&2).unwrap().iter().any(|c| *c == Card::J) {
-                    return DeckScore::FourOfAKind;
-                }
-            }
-            DeckScore::OnePair => {
-                if deck.cards.iter().any(|c| *c == Card::J) {
-                    return DeckScore::ThreeOfAKind;
-                }
-            }
-            DeckScore::HighCard => {
-                if deck.cards.iter().any(|c| *c == Card::J) {
-                    return DeckScore::OnePair;
-                }
-            }
-            _ => {}
-        }
-
-        part1
-    }
+/// Rule variant of the game: the strength of every card and which cards act as wildcards
+#[derive(Debug, Clone)]
+pub struct Rules {
+    /// All cards from the weakest to the strongest
+    ordering: Vec<Card>,
+    /// Cards which count as whatever card makes the strongest hand
+    wildcards: Vec<Card>,
 }
 
-impl Deck {
-    fn count(&mut self) {
-        let mut counted: HashMap<usize, Vec<Card>> = HashMap::with_capacity(5);
+impl Rules {
+    /// `ordering` lists every card from weakest to strongest, e.g. `"23456789TJQKA"`
+    pub fn new(ordering: &str, wildcards: &str) -> Self {
+        let ordering = ordering.chars().map(Card::from).collect_vec();
+        assert_eq!(ordering.iter().unique().count(), 13, "every card needs a strength");
 
-        for count in self.cards.iter().counts() {
-            if let Some(existing) = counted.get_mut(&count.1) {
-                existing.push(*count.0);
-            } else {
-                counted.insert(count.1, vec![*count.0]);
-            }
+        Rules {
+            ordering,
+            wildcards: wildcards.chars().map(Card::from).collect(),
         }
-
-        self.counted = Some(counted);
     }
 
-    fn score(&mut self) {
-        self.score = Some(DeckScore::from_deck(self));
+    /// Part 1: no wildcards, jacks rank between tens and queens
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", "")
     }
-    fn score_part2(&mut self) {
-        self.score = Some(DeckScore::from_deck_part2(self));
-    }
-}
 
-impl PartialEq for Deck {
-    fn eq(&self, other: &Self) -> bool {
-        self.cards == other.cards
+    /// Part 2: jokers are wildcards, but the weakest card on their own
+    pub fn jokers() -> Self {
+        Self::new("J23456789TQKA", "J")
     }
-}
 
-impl PartialOrd for Deck {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn strength(&self, card: Card) -> u8 {
+        self.ordering.iter().position(|c| *c == card).unwrap() as u8
     }
-}
 
-impl Ord for Deck {
-    fn cmp(&self, other: &Self) -> Ordering {
-        let mut res = self.score.cmp(&other.score);
-        if res == Ordering::Equal {
-            if self.part2 {
-                res = compare_cards_part2(&self.cards, &other.cards)
-            } else {
-                res = compare_cards(&self.cards, &other.cards)
-            }
-        }
+    /// Type of the hand and the key used to break ties between hands of the same type
+    pub fn evaluate(&self, cards: &[Card; 5]) -> (DeckScore, [u8; 5]) {
+        let wildcards = cards.iter().filter(|c| self.wildcards.contains(c)).count();
+        let mut counts = cards
+            .iter()
+            .filter(|c| !self.wildcards.contains(c))
+            .counts()
+            .into_values()
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .collect_vec();
 
-        res
-    }
-}
-
-#[inline]
-fn compare_cards(a: &[Card], b: &[Card]) -> Ordering {
-    if a.len() != b.len() {
-        panic!("card deck length must be the same")
-    }
+        // Wildcards always make the largest group even larger
+        match counts.first_mut() {
+            Some(largest) => *largest += wildcards,
+            None => counts.push(wildcards),
+        }
 
-    let mut result = Ordering::Equal;
-    for idx in 0..a.len() {
-        result = a[idx].cmp(&b[idx]);
+        let score = match counts[..] {
+            [5] => DeckScore::FiveOfAKind,
+            [4, ..] => DeckScore::FourOfAKind,
+            [3, 2] => DeckScore::FullHouse,
+            [3, ..] => DeckScore::ThreeOfAKind,
+            [2, 2, ..] => DeckScore::TwoPair,
+            [2, ..] => DeckScore::OnePair,
+            _ => DeckScore::HighCard,
+        };
 
-        if result != Ordering::Equal {
-            break;
-        }
+        (score, cards.map(|card| self.strength(card)))
     }
-    result
 }
 
-#[inline]
-fn compare_cards_part2(a: &[Card], b: &[Card]) -> Ordering {
-    if a.len() != b.len() {
-        panic!("card deck length must be the same")
-    }
-
-    let a = a.iter().map(map_part1_part2).collect_vec();
-    let b = b.iter().map(map_part1_part2).collect_vec();
+fn total_winnings(decks: &[Deck], rules: &Rules) -> usize {
+    let mut decks = decks.to_vec();
+    decks.sort_by_cached_key(|deck| rules.evaluate(&deck.cards));
 
-    let mut result = Ordering::Equal;
-    for idx in 0..a.len() {
-        result = a[idx].cmp(&b[idx]);
-
-        if result != Ordering::Equal {
-            break;
-        }
-    }
-    result
+    decks
+        .iter()
+        .enumerate()
+        .map(|deck| (deck.0 + 1) * deck.1.bid as usize)
+        .sum()
 }
 
 #[aoc_generator(day7)]
@@ -253,9 +146,6 @@ pub fn parse_input(input: &str) -> ParseResult {
             |(cards, bid)| Deck {
                 bid,
                 cards: std::array::from_fn(|c| cards[c]),
-                counted: None,
-                score: None,
-                part2: false,
             },
         ),
     )(input.trim());
@@ -265,40 +155,12 @@ pub fn parse_input(input: &str) -> ParseResult {
 
 #[aoc(day7, part1)]
 pub fn part1(input: &ParseResult) -> usize {
-    let mut decks = input.clone();
-    decks.iter_mut().for_each(Deck::count);
-    decks.iter_mut().for_each(Deck::score);
-    decks.sort();
-
-    // for deck in &decks {
-    //     println!("{}, {:?}", deck.cards.iter().join(""), deck.score.as_ref().unwrap());
-    // }
-
-    decks
-        .iter()
-        .enumerate()
-        .map(|deck| (deck.0 + 1) * deck.1.bid as usize)
-        .sum()
+    total_winnings(input, &Rules::standard())
 }
 
 #[aoc(day7, part2)]
 pub fn part2(input: &ParseResult) -> usize {
-    let mut decks = input.clone();
-
-    decks.iter_mut().for_each(|d| d.part2 = true);
-    decks.iter_mut().for_each(Deck::count);
-    decks.iter_mut().for_each(Deck::score_part2);
-    decks.sort();
-
-    // for deck in &decks {
-    //     println!("{}, {:?}", deck.cards.iter().join(""), deck.score.as_ref().unwrap());
-    // }
-
-    decks
-        .iter()
-        .enumerate()
-        .map(|deck| (deck.0 + 1) * deck.1.bid as usize)
-        .sum()
+    total_winnings(input, &Rules::jokers())
 }
 
 #[cfg(test)]
@@ -331,4 +193,25 @@ mod tests {
         let input = parse_input(INPUT);
         assert_eq!(part2(&input), 254494947);
     }
+
+    #[test]
+    fn rule_variants() {
+        let hand = |cards: &str| -> [Card; 5] { std::array::from_fn(|idx| Card::from(cards.as_bytes()[idx] as char)) };
+
+        let standard = Rules::standard();
+        assert_eq!(standard.evaluate(&hand("KTJJT")).0, DeckScore::TwoPair);
+        assert_eq!(standard.evaluate(&hand("23332")).0, DeckScore::FullHouse);
+        assert!(standard.evaluate(&hand("33332")) > standard.evaluate(&hand("2AAAA")));
+
+        let jokers = Rules::jokers();
+        assert_eq!(jokers.evaluate(&hand("KTJJT")).0, DeckScore::FourOfAKind);
+        assert_eq!(jokers.evaluate(&hand("JJJJJ")).0, DeckScore::FiveOfAKind);
+        assert!(jokers.evaluate(&hand("JKKK2")) < jokers.evaluate(&hand("QQQQ2")));
+
+        // Twos are wild as well and aces are the weakest card
+        let deuces = Rules::new("A23456789TJQK", "J2");
+        assert_eq!(deuces.evaluate(&hand("2J3A4")).0, DeckScore::ThreeOfAKind);
+        assert_eq!(deuces.evaluate(&hand("22JJ5")).0, DeckScore::FiveOfAKind);
+        assert!(deuces.evaluate(&hand("A3456")) < deuces.evaluate(&hand("23456")));
+    }
 }
@@ -1,5 +1,5 @@
 use std::collections::{HashMap, VecDeque};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use dyn_clone::DynClone;
@@ -48,9 +48,17 @@ impl std::ops::Not for Pulse {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    FlipFlop,
+    Broadcast,
+    Conjunction,
+}
+
 pub trait Module: Debug + DynClone {
     fn evaluate(&mut self, pulse: Pulse, from: &str) -> Option<Pulse>;
     fn update_inputs(&mut self, _inputs: HashMap<String, Pulse>) {}
+    fn kind(&self) -> ModuleKind;
 }
 
 impl Module for FlipFlo {
@@ -63,12 +71,20 @@ impl Module for FlipFlo {
             (Pulse::High, _) => None,
         }
     }
+
+    fn kind(&self) -> ModuleKind {
+        ModuleKind::FlipFlop
+    }
 }
 
 impl Module for Broadcast {
     fn evaluate(&mut self, pulse: Pulse, _from: &str) -> Option<Pulse> {
         Some(pulse)
     }
+
+    fn kind(&self) -> ModuleKind {
+        ModuleKind::Broadcast
+    }
 }
 
 impl Module for Conjunction {
@@ -89,6 +105,10 @@ impl Module for Conjunction {
     fn update_inputs(&mut self, inputs: HashMap<String, Pulse>) {
         self.input_states = inputs;
     }
+
+    fn kind(&self) -> ModuleKind {
+        ModuleKind::Conjunction
+    }
 }
 
 #[aoc_generator(day20)]
@@ -185,70 +205,178 @@ fn run_to_end(
     (low_pulses, hight_pulses)
 }
 
-#[aoc(day20, part1)]
-pub fn part1(input: &ParseResult) -> usize {
-    let mut graph = input.clone();
+/// Graphviz representation of the circuit, flip-flops are boxes and conjunctions diamonds
+pub fn to_dot(graph: &Graph) -> String {
+    let mut dot = String::from("digraph circuit {\n");
 
-    let mut low_pulses = 0;
-    let mut hight_pulses = 0;
+    for (name, node) in graph.iter().sorted_by_key(|(name, _)| *name) {
+        let (label, shape) = match node.module.kind() {
+            ModuleKind::FlipFlop => (format!("%{name}"), "box"),
+            ModuleKind::Conjunction => (format!("&{name}"), "diamond"),
+            ModuleKind::Broadcast => (name.clone(), "doublecircle"),
+        };
+        dot += &format!("    {name} [label=\"{label}\", shape={shape}];\n");
+    }
 
-    for _ in 0..1_000 {
-        let (low, high) = run_to_end("button", "broadcaster", Pulse::Low, &mut graph, |_, _, _| {});
-        low_pulses += low;
-        hight_pulses += high;
+    for (name, node) in graph.iter().sorted_by_key(|(name, _)| *name) {
+        for output in &node.outputs {
+            dot += &format!("    {name} -> {output};\n");
+        }
     }
 
-    low_pulses * hight_pulses
+    dot + "}\n"
 }
 
-#[aoc(day20, part2)]
-pub fn part2(input: &ParseResult) -> isize {
-    let mut graph = input.clone();
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PulseCounter {
+    pub low: usize,
+    pub high: usize,
+}
 
-    // General solutions is not possible with brute force
-    // The graph has four areas which input to the final state
-    // Determine the number of button presses required for each area
+/// Pulses received by every module (including untyped ones like `rx`) over `presses` button presses
+pub fn count_pulses(graph: &mut Graph, presses: usize) -> HashMap<String, PulseCounter> {
+    let mut counters: HashMap<String, PulseCounter> = HashMap::new();
 
-    let areas = graph.get("broadcaster").unwrap().outputs.clone();
-    let src = graph
-        .iter()
-        .find_map(|module| {
-            if module.1.outputs.contains(&String::from("rx")) {
-                Some(module.0)
-            } else {
-                None
+    for _ in 0..presses {
+        run_to_end("button", "broadcaster", Pulse::Low, graph, |_, to, pulse| {
+            let counter = counters.entry(to.to_string()).or_default();
+            match pulse {
+                Pulse::Low => counter.low += 1,
+                Pulse::High => counter.high += 1,
+            }
+        });
+    }
+
+    counters
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CycleError {
+    /// The target has to be fed by exactly one module
+    NoSingleFeeder {
+        target: String,
+        feeders: Vec<String>,
+    },
+    NotAConjunction {
+        feeder: String,
+    },
+    /// An input of the conjunction never sent a high pulse
+    NoHighPulse {
+        input: String,
+        presses: usize,
+    },
+    /// An input sends high pulses, but not every `period` presses starting at the first press
+    NotPeriodic {
+        input: String,
+        first: usize,
+        second: usize,
+    },
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CycleError::NoSingleFeeder { target, feeders } => {
+                write!(f, "{target} is fed by {feeders:?} instead of exactly one module")
+            }
+            CycleError::NotAConjunction { feeder } => write!(f, "{feeder} is not a conjunction"),
+            CycleError::NoHighPulse { input, presses } => {
+                write!(f, "{input} sent no high pulse within {presses} button presses")
             }
-        })
-        .unwrap()
-        .clone();
+            CycleError::NotPeriodic { input, first, second } => write!(
+                f,
+                "{input} sent high pulses after {first} and {second} presses, which is no cycle starting at 0"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CycleError {}
 
-    areas
+/// Button presses until `target` receives a low pulse.
+///
+/// Assumes the target is fed by a single conjunction, which sends a low pulse once all its inputs sent a
+/// high pulse during the same press. Every input has to do that periodically, so the answer is the LCM
+/// of the periods.
+pub fn presses_until_low(graph: &Graph, target: &str, max_presses: usize) -> Result<usize, CycleError> {
+    let feeders = graph
         .iter()
-        .map(|area| {
-            let mut found = false;
-            let mut button_presses = 0;
-
-            while !found {
-                button_presses += 1;
-
-                run_to_end(
-                    "broadcaster",
-                    area,
-                    Pulse::Low,
-                    &mut graph,
-                    |_from: &str, to: &str, pulse: Pulse| {
-                        if to == src && pulse == Pulse::High {
-                            found = true;
-                            //println!("{} -> {} {:?}", from, to, pulse);
-                        }
-                    },
-                );
+        .filter(|(_, node)| node.outputs.iter().any(|output| output == target))
+        .map(|(name, _)| name.clone())
+        .sorted()
+        .collect::<Vec<_>>();
+
+    let [feeder] = feeders.as_slice() else {
+        return Err(CycleError::NoSingleFeeder {
+            target: target.to_string(),
+            feeders,
+        });
+    };
+
+    if graph[feeder].module.kind() != ModuleKind::Conjunction {
+        return Err(CycleError::NotAConjunction { feeder: feeder.clone() });
+    }
+
+    let inputs = graph
+        .iter()
+        .filter(|(_, node)| node.outputs.contains(feeder))
+        .map(|(name, _)| name.clone())
+        .sorted()
+        .collect::<Vec<_>>();
+
+    let mut high_pulses: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut graph = graph.clone();
+
+    for presses in 1..=max_presses {
+        run_to_end("button", "broadcaster", Pulse::Low, &mut graph, |from, to, pulse| {
+            if to == feeder && pulse == Pulse::High {
+                if let Some(input) = inputs.iter().find(|input| *input == from) {
+                    let seen = high_pulses.entry(input).or_default();
+                    if seen.last() != Some(&presses) {
+                        seen.push(presses);
+                    }
+                }
             }
+        });
+
+        if inputs
+            .iter()
+            .all(|input| high_pulses.get(input.as_str()).is_some_and(|seen| seen.len() >= 2))
+        {
+            break;
+        }
+    }
 
-            button_presses
-        })
-        .reduce(|acc, b| acc.lcm(&b))
-        .unwrap()
+    inputs.iter().try_fold(1, |lcm: usize, input| {
+        match high_pulses.get(input.as_str()).map(Vec::as_slice) {
+            Some(&[first, second, ..]) if second == 2 * first => Ok(lcm.lcm(&first)),
+            Some(&[first, second, ..]) => Err(CycleError::NotPeriodic {
+                input: input.clone(),
+                first,
+                second,
+            }),
+            _ => Err(CycleError::NoHighPulse {
+                input: input.clone(),
+                presses: max_presses,
+            }),
+        }
+    })
+}
+
+#[aoc(day20, part1)]
+pub fn part1(input: &ParseResult) -> usize {
+    let mut graph = input.clone();
+
+    let (low_pulses, hight_pulses) = count_pulses(&mut graph, 1_000)
+        .values()
+        .fold((0, 0), |(low, high), counter| (low + counter.low, high + counter.high));
+
+    low_pulses * hight_pulses
+}
+
+#[aoc(day20, part2)]
+pub fn part2(input: &ParseResult) -> usize {
+    presses_until_low(input, "rx", 100_000).unwrap_or_else(|error| panic!("{error}"))
 }
 
 #[cfg(test)]
@@ -282,4 +410,48 @@ mod tests {
         let input = parse_input(INPUT);
         assert_eq!(part2(&input), 238420328103151);
     }
+
+    #[test]
+    fn dot_export() {
+        let input = parse_input(EXAMPLE2);
+        let dot = to_dot(&input);
+
+        assert!(dot.starts_with("digraph circuit {\n    a [label=\"%a\", shape=box];\n"));
+        assert!(dot.contains("    con [label=\"&con\", shape=diamond];\n"));
+        assert!(dot.contains("    broadcaster -> a;\n"));
+        assert!(dot.ends_with("    inv -> b;\n}\n"));
+    }
+
+    #[test]
+    fn pulse_counters() {
+        let mut input = parse_input(EXAMPLE2);
+        let counters = count_pulses(&mut input, 1);
+
+        assert_eq!(counters["output"], PulseCounter { low: 1, high: 1 });
+        assert_eq!(counters["con"], PulseCounter { low: 0, high: 2 });
+    }
+
+    #[test]
+    fn no_cycle_structure() {
+        let input = parse_input(EXAMPLE1);
+        assert_eq!(
+            presses_until_low(&input, "rx", 1_000),
+            Err(CycleError::NoSingleFeeder {
+                target: "rx".to_string(),
+                feeders: vec![]
+            })
+        );
+
+        let input = parse_input(EXAMPLE2);
+        assert_eq!(
+            presses_until_low(&input, "inv", 1_000),
+            Err(CycleError::NotAConjunction {
+                feeder: "a".to_string()
+            })
+        );
+        assert!(matches!(
+            presses_until_low(&input, "output", 1_000),
+            Err(CycleError::NotPeriodic { .. })
+        ));
+    }
 }
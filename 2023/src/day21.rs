@@ -104,10 +104,90 @@ pub fn part1(input: &ParseResult) -> usize {
     part1_solver(input, 64)
 }
 
+/// Number of plots first reached after exactly `d` steps on the infinitely repeated garden, for every `d`.
+///
+/// On a grid every neighbor is one step closer or one step further away, so only the last two
+/// layers of the BFS have to be kept to not walk back.
+fn layer_sizes(input: &ParseResult, max_steps: usize) -> Vec<usize> {
+    let size = ivec2(input.garden.ncols() as i32, input.garden.nrows() as i32);
+    let is_plot = |pos: IVec2| {
+        let tile = pos.rem_euclid(size);
+        input.garden[[tile.y as usize, tile.x as usize]] != Shape::Rock
+    };
+
+    let mut previous = HashSet::new();
+    let mut current = HashSet::from([input.start]);
+    let mut sizes = vec![1];
+
+    while sizes.len() <= max_steps && !current.is_empty() {
+        let next = current
+            .iter()
+            .flat_map(|pos| ADJACENT.iter().map(move |direction| *pos + *direction))
+            .filter(|pos| !previous.contains(pos) && !current.contains(pos) && is_plot(*pos))
+            .collect::<HashSet<_>>();
+
+        sizes.push(next.len());
+        previous = std::mem::replace(&mut current, next);
+    }
+
+    sizes
+}
+
+/// Plots which can be reached in exactly `steps`: every plot at a distance with the same parity,
+/// because walking back and forth wastes two steps
+fn count_reachable(layers: &[usize], steps: usize) -> usize {
+    layers.iter().take(steps + 1).skip(steps % 2).step_by(2).sum()
+}
+
+/// Exact number of plots reached after `steps` on the infinite garden
+pub fn infinite_solver(input: &ParseResult, steps: usize) -> usize {
+    count_reachable(&layer_sizes(input, steps), steps)
+}
+
+/// Periods of the garden which are walked at most while looking for a quadratic growth
+const MAX_PERIODS: usize = 16;
+
+/// Once the walk covers whole copies of the garden, the count grows quadratically with every
+/// `size` steps. The growth is detected on small step counts and extrapolated to `steps`, the
+/// walked distance is doubled until the growth is stable.
+///
+/// `None` if the growth never becomes quadratic within `MAX_PERIODS` repetitions.
+pub fn extrapolated_solver(input: &ParseResult, steps: usize) -> Option<usize> {
+    let size = input.garden.nrows();
+    assert_eq!(size, input.garden.ncols(), "the garden must be square");
+
+    let offset = steps % size;
+    let periods = steps / size;
+
+    let mut walked = 4;
+    while walked <= MAX_PERIODS {
+        let layers = layer_sizes(input, offset + walked * size);
+        if periods <= walked {
+            return Some(count_reachable(&layers, steps));
+        }
+
+        let samples = (0..=walked)
+            .map(|period| count_reachable(&layers, offset + period * size) as i64)
+            .collect::<Vec<_>>();
+        let second_differences = samples.windows(3).map(|w| w[2] - 2 * w[1] + w[0]).collect::<Vec<_>>();
+
+        // The first period from which on the second difference stays the same
+        if let Some(first) = second_differences.windows(3).position(|w| w[0] == w[1] && w[1] == w[2]) {
+            let n = (periods - first) as i64;
+            let first_difference = samples[first + 1] - samples[first];
+            let value = samples[first] + n * first_difference + n * (n - 1) / 2 * second_differences[first];
+            return Some(value as usize);
+        }
+
+        walked *= 2;
+    }
+
+    None
+}
+
 #[aoc(day21, part2)]
-pub fn part2(_input: &ParseResult) -> usize {
-    todo!()
-    //part1_solver(input, 26501365)
+pub fn part2(input: &ParseResult) -> usize {
+    extrapolated_solver(input, 26501365).expect("the garden does not grow quadratically")
 }
 
 #[cfg(test)]
@@ -129,15 +209,29 @@ mod tests {
         assert_eq!(part1(&input), 3733);
     }
 
-    //#[test]
+    #[test]
     fn example_part2() {
         let input = parse_input(EXAMPLE);
-        assert_eq!(part2(&input), todo!());
+
+        for (steps, plots) in [(6, 16), (10, 50), (50, 1594), (100, 6536), (500, 167004)] {
+            assert_eq!(infinite_solver(&input, steps), plots);
+        }
+        assert_eq!(extrapolated_solver(&input, 1000), Some(668697));
+        assert_eq!(extrapolated_solver(&input, 5000), Some(16733044));
     }
 
-    //#[test]
+    #[test]
     fn input_part2() {
         let input = parse_input(INPUT);
-        assert_eq!(part2(&input), 42);
+        assert_eq!(part2(&input), 617729401414635);
+    }
+
+    #[test]
+    fn extrapolation_matches_walking() {
+        let input = parse_input(INPUT);
+        assert_eq!(infinite_solver(&input, 64), part1(&input));
+
+        let steps = 65 + 131 * 5;
+        assert_eq!(extrapolated_solver(&input, steps), Some(infinite_solver(&input, steps)));
     }
 }
@@ -1,5 +1,4 @@
-use std::{cmp::Ordering, collections::HashSet};
-
+use aoc_common::graph::{Graph, NodeId};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
@@ -7,8 +6,8 @@ type Number = u16;
 
 #[derive(Debug)]
 pub struct ParseResult {
-    rules: HashSet<(Number, Number)>,
-    updates: Vec<Vec<Number>>,
+    rules: Graph<Number>,
+    updates: Vec<Vec<NodeId>>,
 }
 
 #[aoc_generator(day5)]
@@ -16,25 +15,27 @@ pub fn parse_input(input: &str) -> ParseResult {
     let input = input.trim().replace('\r', "");
     let (rules, updates) = input.split_once("\n\n").unwrap();
 
-    let rules = rules
-        .lines()
-        .map(|line| {
-            line.split_once('|')
-                .map(|(a, b)| (a.parse::<Number>().unwrap(), b.parse::<Number>().unwrap()))
-                .unwrap()
-        })
-        .collect::<HashSet<_>>();
+    let mut graph = Graph::new();
+
+    for line in rules.lines() {
+        let (a, b) = line
+            .split_once('|')
+            .map(|(a, b)| (a.parse::<Number>().unwrap(), b.parse::<Number>().unwrap()))
+            .unwrap();
+
+        graph.add_edge(&a, &b);
+    }
 
     let updates = updates
         .lines()
         .map(|line| {
             line.split(',')
-                .map(|nb| nb.trim().parse::<Number>().unwrap())
+                .map(|nb| graph.add_node(&nb.trim().parse::<Number>().unwrap()))
                 .collect::<Vec<_>>()
         })
         .collect::<Vec<_>>();
 
-    ParseResult { rules, updates }
+    ParseResult { rules: graph, updates }
 }
 
 #[aoc(day5, part1)]
@@ -43,7 +44,7 @@ pub fn part1(input: &ParseResult) -> Number {
         .updates
         .iter()
         .filter(|update| is_sorted(update, &input.rules))
-        .map(|update| update[update.len() / 2])
+        .map(|update| *input.rules.key(update[update.len() / 2]))
         .sum()
 }
 
@@ -57,27 +58,16 @@ pub fn part2(input: &ParseResult) -> Number {
         .sum()
 }
 
-fn is_sorted(update: &[Number], rules: &HashSet<(Number, Number)>) -> bool {
-    for (a, b) in update.iter().tuple_windows() {
-        if !rules.contains(&(*a, *b)) {
-            return false;
-        }
-    }
-    true
+fn is_sorted(update: &[NodeId], rules: &Graph<Number>) -> bool {
+    update.iter().tuple_windows().all(|(a, b)| rules.has_edge(*a, *b))
 }
 
-fn sort(update: &[Number], rules: &HashSet<(Number, Number)>) -> Number {
-    let mut update = update.to_vec();
-
-    update.sort_unstable_by(|a, b| {
-        if rules.contains(&(*a, *b)) {
-            Ordering::Greater
-        } else {
-            Ordering::Less
-        }
-    });
+fn sort(update: &[NodeId], rules: &Graph<Number>) -> Number {
+    let update = rules
+        .topological_sort_subset(update)
+        .expect("the rules for an update must not contain a cycle");
 
-    update[update.len() / 2]
+    *rules.key(update[update.len() / 2])
 }
 
 #[cfg(test)]
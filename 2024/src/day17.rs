@@ -1,4 +1,9 @@
+use std::fmt;
+
+use aoc_common::parse::{comma_separated, labeled, parse_all, signed};
 use aoc_runner_derive::{aoc, aoc_generator};
+use nom::character::complete::{line_ending, multispace1};
+use nom::sequence::{separated_pair, terminated, tuple};
 
 type Number = i64;
 type ParseResult = BitComputer;
@@ -16,41 +21,12 @@ pub struct BitComputer {
 
 #[aoc_generator(day17)]
 pub fn parse_input(input: &str) -> ParseResult {
-    let mut lines = input.trim().lines();
-
-    let reg_a = lines
-        .next()
-        .unwrap()
-        .strip_prefix("Register A: ")
-        .unwrap()
-        .parse()
-        .unwrap();
-    let reg_b = lines
-        .next()
-        .unwrap()
-        .strip_prefix("Register B: ")
-        .unwrap()
-        .parse()
-        .unwrap();
-    let reg_c = lines
-        .next()
-        .unwrap()
-        .strip_prefix("Register C: ")
-        .unwrap()
-        .parse()
-        .unwrap();
-
-    // Consume empty line
-    _ = lines.next();
-
-    let program = lines
-        .next()
-        .unwrap()
-        .strip_prefix("Program: ")
-        .unwrap()
-        .split(',')
-        .map(|s| s.trim().parse().unwrap())
-        .collect();
+    let register = |name| terminated(labeled(name, signed::<Number>), line_ending);
+    let registers = tuple((register("Register A"), register("Register B"), register("Register C")));
+    let program = labeled("Program", comma_separated::<Number>);
+
+    let ((reg_a, reg_b, reg_c), program) =
+        parse_all(separated_pair(registers, multispace1, program), input.trim()).unwrap();
 
     ParseResult {
         program,
@@ -59,135 +35,416 @@ pub fn parse_input(input: &str) -> ParseResult {
     }
 }
 
+/// Combo operand, literal values 0-3 or the value of a register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combo {
+    Literal(Number),
+    Register(usize),
+    Reserved,
+}
+
+impl Combo {
+    fn decode(operand: Number) -> Self {
+        match operand {
+            0..=3 => Combo::Literal(operand),
+            4 => Combo::Register(REG_A),
+            5 => Combo::Register(REG_B),
+            6 => Combo::Register(REG_C),
+            _ => Combo::Reserved,
+        }
+    }
+}
+
+impl fmt::Display for Combo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Combo::Literal(value) => write!(f, "{value}"),
+            Combo::Register(reg) => write!(f, "{}", ['A', 'B', 'C'][*reg]),
+            Combo::Reserved => write!(f, "<reserved>"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Adv(Combo),
+    Bxl(Number),
+    Bst(Combo),
+    Jnz(Number),
+    Bxc,
+    Out(Combo),
+    Bdv(Combo),
+    Cdv(Combo),
+}
+
+impl Instruction {
+    fn decode(opcode: Number, operand: Number) -> Option<Self> {
+        let combo = Combo::decode(operand);
+
+        Some(match opcode {
+            0 => Instruction::Adv(combo),
+            1 => Instruction::Bxl(operand),
+            2 => Instruction::Bst(combo),
+            3 => Instruction::Jnz(operand),
+            4 => Instruction::Bxc,
+            5 => Instruction::Out(combo),
+            6 => Instruction::Bdv(combo),
+            7 => Instruction::Cdv(combo),
+            _ => return None,
+        })
+    }
+
+    /// What the instruction does in pseudo code
+    fn effect(&self) -> String {
+        match self {
+            Instruction::Adv(combo) => format!("A = A >> {combo}"),
+            Instruction::Bxl(literal) => format!("B = B ^ {literal}"),
+            Instruction::Bst(combo) => format!("B = {combo} % 8"),
+            Instruction::Jnz(literal) => format!("if A != 0 jump {literal}"),
+            Instruction::Bxc => "B = B ^ C".to_string(),
+            Instruction::Out(combo) => format!("output {combo} % 8"),
+            Instruction::Bdv(combo) => format!("B = A >> {combo}"),
+            Instruction::Cdv(combo) => format!("C = A >> {combo}"),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Adv(combo) => write!(f, "adv {combo}"),
+            Instruction::Bxl(literal) => write!(f, "bxl {literal}"),
+            Instruction::Bst(combo) => write!(f, "bst {combo}"),
+            Instruction::Jnz(literal) => write!(f, "jnz {literal}"),
+            Instruction::Bxc => write!(f, "bxc"),
+            Instruction::Out(combo) => write!(f, "out {combo}"),
+            Instruction::Bdv(combo) => write!(f, "bdv {combo}"),
+            Instruction::Cdv(combo) => write!(f, "cdv {combo}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmError {
+    InvalidOpcode { pc: usize, opcode: Number },
+    InvalidOperand { pc: usize, operand: Number },
+    MissingOperand { pc: usize },
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::InvalidOpcode { pc, opcode } => write!(f, "invalid opcode {opcode} at {pc}"),
+            VmError::InvalidOperand { pc, operand } => write!(f, "invalid combo operand {operand} at {pc}"),
+            VmError::MissingOperand { pc } => write!(f, "missing operand at {pc}"),
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
+
 #[derive(Debug, Clone)]
 enum OpcodeResult {
     Output(Number),
     NoOutput,
     Halt,
-    Error(String),
+    Error(VmError),
 }
 
 impl BitComputer {
+    fn instruction(&self, pc: usize) -> Result<Instruction, VmError> {
+        let opcode = self.program[pc];
+        let Some(&operand) = self.program.get(pc + 1) else {
+            return Err(VmError::MissingOperand { pc });
+        };
+
+        Instruction::decode(opcode, operand).ok_or(VmError::InvalidOpcode { pc, opcode })
+    }
+
+    /// One line per instruction, e.g. ` 0: adv 3    ; A = A >> 3`
+    pub fn disassemble(&self) -> String {
+        (0..self.program.len())
+            .step_by(2)
+            .map(|pc| match self.instruction(pc) {
+                Ok(instruction) => format!("{pc:>2}: {:<8} ; {}", instruction.to_string(), instruction.effect()),
+                Err(error) => format!("{pc:>2}: ???      ; {error}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     fn run_opcode(&mut self) -> OpcodeResult {
         if self.pc >= self.program.len() {
             return OpcodeResult::Halt;
         }
 
-        let opcode = self.program[self.pc];
-        let operand = self.get_operand(self.pc + 1);
+        match self.execute() {
+            Ok(Some(output)) => OpcodeResult::Output(output),
+            Ok(None) => OpcodeResult::NoOutput,
+            Err(error) => OpcodeResult::Error(error),
+        }
+    }
 
-        match opcode {
-            0 => {
-                // ADV instruction#
-                self.reg[REG_A] /= 2i64.pow(operand as u32);
-                self.pc += 2;
-            }
-            1 => {
-                // BXL instruction
-                self.reg[REG_B] ^= self.program[self.pc + 1];
-                self.pc += 2;
-            }
-            2 => {
-                // BST instruction
-                self.reg[REG_B] = operand % 8;
-                self.pc += 2;
-            }
-            3 => {
-                // JNZ instruction
-                if self.reg[REG_A] != 0 {
-                    self.pc = operand as usize;
-                } else {
-                    self.pc += 2;
+    /// Execute the instruction at `pc`, the state is unchanged if it fails
+    fn execute(&mut self) -> Result<Option<Number>, VmError> {
+        let pc = self.pc;
+        let instruction = self.instruction(pc)?;
+        let combo = |combo| self.combo(combo, pc);
+        let shifted = |shift| shr(self.reg[REG_A], shift);
+
+        let mut reg = self.reg;
+        let mut next_pc = pc + 2;
+        let mut output = None;
+
+        match instruction {
+            Instruction::Adv(operand) => reg[REG_A] = shifted(combo(operand)?),
+            Instruction::Bxl(literal) => reg[REG_B] ^= literal,
+            Instruction::Bst(operand) => reg[REG_B] = combo(operand)? % 8,
+            Instruction::Jnz(literal) => {
+                if reg[REG_A] != 0 {
+                    next_pc = literal as usize;
                 }
             }
-            4 => {
-                // BXC instruction
-                self.reg[REG_B] ^= self.reg[REG_C];
-                self.pc += 2;
-            }
-            5 => {
-                // OUT instruction
-                self.pc += 2;
-                return OpcodeResult::Output(operand % 8);
-            }
-            6 => {
-                // BDV instruction
-                self.reg[REG_B] = self.reg[REG_A] / 2i64.pow(operand as u32);
-                self.pc += 2;
-            }
-            7 => {
-                // CDV instruction
-                self.reg[REG_C] = self.reg[REG_A] / 2i64.pow(operand as u32);
-                self.pc += 2;
-            }
+            Instruction::Bxc => reg[REG_B] ^= reg[REG_C],
+            Instruction::Out(operand) => output = Some(combo(operand)? % 8),
+            Instruction::Bdv(operand) => reg[REG_B] = shifted(combo(operand)?),
+            Instruction::Cdv(operand) => reg[REG_C] = shifted(combo(operand)?),
+        }
+
+        self.reg = reg;
+        self.pc = next_pc;
+        Ok(output)
+    }
 
-            _ => return OpcodeResult::Error(format!("Invalid opcode: {opcode}")),
+    fn combo(&self, combo: Combo, pc: usize) -> Result<Number, VmError> {
+        match combo {
+            Combo::Literal(value) => Ok(value),
+            Combo::Register(reg) => Ok(self.reg[reg]),
+            Combo::Reserved => Err(VmError::InvalidOperand {
+                pc,
+                operand: self.program[pc + 1],
+            }),
+        }
+    }
+
+    /// Run the program to completion with register A set to `reg_a`
+    fn output_with(&self, reg_a: Number) -> Result<Vec<Number>, VmError> {
+        let mut computer = self.clone();
+        computer.reg[REG_A] = reg_a;
+
+        let mut result = vec![];
+        loop {
+            match computer.run_opcode() {
+                OpcodeResult::Output(output) => result.push(output),
+                OpcodeResult::Halt => break,
+                OpcodeResult::Error(error) => return Err(error),
+                OpcodeResult::NoOutput => {}
+            }
         }
 
-        OpcodeResult::NoOutput
+        Ok(result)
     }
 
-    fn get_operand(&self, offset: usize) -> Number {
-        let raw_operand = self.program[offset];
+    /// Smallest value for register A which makes the program output itself.
+    ///
+    /// The puzzle programs are a single loop which outputs one value and then shifts A by three bits,
+    /// so the last output only depends on the highest three bits of A, the one before on the highest six
+    /// and so on. Search backwards from the last output, fixing three more bits of A for every output.
+    pub fn find_quine(&self) -> Option<Number> {
+        let shifts = self
+            .program
+            .chunks(2)
+            .filter(|chunk| chunk.len() == 2 && chunk[0] == 0)
+            .collect::<Vec<_>>();
+        if shifts != [[0, 3]] || !self.program.ends_with(&[3, 0]) {
+            // Not the loop described above, the search would be meaningless
+            return None;
+        }
+
+        self.find_quine_from(0, self.program.len())
+    }
 
-        match raw_operand {
-            0..=3 => raw_operand,
-            4 => self.reg[REG_A],
-            5 => self.reg[REG_B],
-            6 => self.reg[REG_C],
-            _ => panic!("Invalid operand: {raw_operand}"),
+    fn find_quine_from(&self, prefix: Number, remaining: usize) -> Option<Number> {
+        if remaining == 0 {
+            return Some(prefix);
         }
+
+        (0..8).find_map(|bits| {
+            let reg_a = (prefix << 3) | bits;
+            if self
+                .output_with(reg_a)
+                .is_ok_and(|output| output == self.program[remaining - 1..])
+            {
+                self.find_quine_from(reg_a, remaining - 1)
+            } else {
+                None
+            }
+        })
     }
 }
 
-#[aoc(day17, part1)]
-pub fn part1(input: &ParseResult) -> String {
-    let mut computer = input.clone();
-    let mut result = vec![];
+/// Division by a power of two, shifting by 64 bits or more leaves nothing
+fn shr(value: Number, shift: Number) -> Number {
+    u32::try_from(shift)
+        .ok()
+        .and_then(|shift| value.checked_shr(shift))
+        .unwrap_or(0)
+}
+
+/// State after one executed instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub pc: usize,
+    pub opcode: Number,
+    pub operand: Number,
+    /// Registers after the instruction was executed
+    pub reg: [Number; 3],
+    pub output: Option<Number>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop before the instruction at this address is executed
+    Pc(usize),
+    /// Stop after this value was output
+    Output(Number),
+}
 
-    loop {
-        match computer.run_opcode() {
-            OpcodeResult::Output(output) => result.push(output),
-            OpcodeResult::Halt => break,
-            OpcodeResult::Error(error) => panic!("Error: {error}"),
-            OpcodeResult::NoOutput => {}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Breakpoint(Breakpoint),
+    BudgetExhausted,
+    Error(VmError),
+}
+
+/// Runs a `BitComputer` with breakpoints, an instruction budget and an optional trace
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    computer: BitComputer,
+    breakpoints: Vec<Breakpoint>,
+    budget: Option<usize>,
+    executed: usize,
+    output: Vec<Number>,
+    trace: Option<Vec<TraceStep>>,
+    /// Pc of the last breakpoint stop, so `run` does not stop there again right away
+    resumed_from: Option<usize>,
+}
+
+impl Debugger {
+    pub fn new(computer: BitComputer) -> Self {
+        Self {
+            computer,
+            breakpoints: vec![],
+            budget: None,
+            executed: 0,
+            output: vec![],
+            trace: None,
+            resumed_from: None,
         }
     }
 
-    result.iter().map(std::string::ToString::to_string).collect::<Vec<String>>().join(",")
-}
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.push(breakpoint);
+        self
+    }
 
-#[aoc(day17, part2)]
-pub fn part2(input: &ParseResult) -> usize {
-    let expeted_output = input.program.clone();
+    /// Stop after `instructions` executed instructions, programs may loop forever
+    pub fn with_budget(mut self, instructions: usize) -> Self {
+        self.budget = Some(instructions);
+        self
+    }
 
-    for idx in 0.. {
-        //println!("idx: {}", idx);
-        let mut computer = input.clone();
-        computer.reg[REG_A] = idx as i64;
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(vec![]);
+        self
+    }
 
-        let mut result = vec![];
+    pub fn computer(&self) -> &BitComputer {
+        &self.computer
+    }
+
+    pub fn output(&self) -> &[Number] {
+        &self.output
+    }
+
+    /// Empty if tracing is disabled
+    pub fn trace(&self) -> &[TraceStep] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    pub fn executed(&self) -> usize {
+        self.executed
+    }
+
+    /// Execute a single instruction, ignoring breakpoints on the current pc
+    pub fn step(&mut self) -> Option<Stop> {
+        if self.budget.is_some_and(|budget| self.executed >= budget) {
+            return Some(Stop::BudgetExhausted);
+        }
+
+        self.resumed_from = None;
+        let pc = self.computer.pc;
+        let output = match self.computer.run_opcode() {
+            OpcodeResult::Halt => return Some(Stop::Halted),
+            OpcodeResult::Error(error) => return Some(Stop::Error(error)),
+            OpcodeResult::Output(output) => Some(output),
+            OpcodeResult::NoOutput => None,
+        };
+        self.executed += 1;
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceStep {
+                pc,
+                opcode: self.computer.program[pc],
+                operand: self.computer.program[pc + 1],
+                reg: self.computer.reg,
+                output,
+            });
+        }
 
+        let output = output?;
+        self.output.push(output);
+        self.breakpoints
+            .contains(&Breakpoint::Output(output))
+            .then_some(Stop::Breakpoint(Breakpoint::Output(output)))
+    }
+
+    /// Run until the program halts, fails, hits a breakpoint or exhausts the budget.
+    ///
+    /// Calling it again after a breakpoint continues behind it.
+    pub fn run(&mut self) -> Stop {
         loop {
-            match computer.run_opcode() {
-                OpcodeResult::Output(output) => result.push(output),
-                OpcodeResult::Halt => break,
-                OpcodeResult::Error(error) => panic!("Error: {error}"),
-                OpcodeResult::NoOutput => {}
+            let pc = self.computer.pc;
+            let breakpoint = Breakpoint::Pc(pc);
+            if self.resumed_from != Some(pc) && self.breakpoints.contains(&breakpoint) {
+                self.resumed_from = Some(pc);
+                return Stop::Breakpoint(breakpoint);
             }
 
-            if result == expeted_output {
-                return idx;
-            }
-            else if expeted_output.starts_with(&result) {
-                continue
+            if let Some(stop) = self.step() {
+                return stop;
             }
-
-            break;
         }
     }
+}
 
-    unreachable!()
+#[aoc(day17, part1)]
+pub fn part1(input: &ParseResult) -> String {
+    let result = input
+        .output_with(input.reg[REG_A])
+        .unwrap_or_else(|error| panic!("Error: {error}"));
+
+    result
+        .iter()
+        .map(std::string::ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[aoc(day17, part2)]
+pub fn part2(input: &ParseResult) -> usize {
+    input.find_quine().expect("Program is not a quine candidate") as usize
 }
 
 #[cfg(test)]
@@ -219,6 +476,96 @@ mod tests {
     #[test]
     fn input_part2() {
         let input = parse_input(INPUT);
-        assert_eq!(part2(&input), 10);
+        let reg_a = part2(&input);
+
+        assert_eq!(input.output_with(reg_a as Number), Ok(input.program.clone()));
+    }
+
+    #[test]
+    fn disassemble() {
+        let input = parse_input(EXAMPLE_2);
+        assert_eq!(
+            input.disassemble(),
+            " 0: adv 3    ; A = A >> 3\n 2: out A    ; output A % 8\n 4: jnz 0    ; if A != 0 jump 0"
+        );
+        assert_eq!(
+            parse_input("Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 1,7,7,5").disassemble(),
+            " 0: bxl 7    ; B = B ^ 7\n 2: cdv B    ; C = A >> B"
+        );
+    }
+
+    #[test]
+    fn quine_search_rejects_other_programs() {
+        let input = parse_input(EXAMPLE_1);
+        assert_eq!(input.find_quine(), None);
+    }
+
+    #[test]
+    fn trace_and_breakpoints() {
+        let mut debugger = Debugger::new(parse_input(EXAMPLE_1))
+            .with_trace()
+            .with_breakpoint(Breakpoint::Output(5))
+            .with_breakpoint(Breakpoint::Pc(4));
+
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Pc(4)));
+        assert_eq!(
+            debugger.trace(),
+            [
+                TraceStep {
+                    pc: 0,
+                    opcode: 0,
+                    operand: 1,
+                    reg: [364, 0, 0],
+                    output: None
+                },
+                TraceStep {
+                    pc: 2,
+                    opcode: 5,
+                    operand: 4,
+                    reg: [364, 0, 0],
+                    output: Some(4)
+                }
+            ]
+        );
+
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Pc(4)));
+        assert_eq!(debugger.output(), [4, 6]);
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Pc(4)));
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Output(5)));
+        assert_eq!(debugger.output(), [4, 6, 3, 5]);
+
+        // A breakpoint on the starting pc stops before the first instruction
+        let mut debugger = Debugger::new(parse_input(EXAMPLE_1)).with_breakpoint(Breakpoint::Pc(0));
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Pc(0)));
+        assert_eq!(debugger.executed(), 0);
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Pc(0)));
+        assert_eq!(debugger.executed(), 3);
+        assert_eq!(debugger.output(), [4]);
+
+        // Stepping back to the breakpoint by hand stops there again
+        for _ in 0..3 {
+            debugger.step();
+        }
+        assert_eq!(debugger.run(), Stop::Breakpoint(Breakpoint::Pc(0)));
+        assert_eq!(debugger.executed(), 6);
+    }
+
+    #[test]
+    fn budget_and_errors() {
+        let endless = parse_input("Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: 3,0");
+        let mut debugger = Debugger::new(endless).with_budget(100);
+        assert_eq!(debugger.run(), Stop::BudgetExhausted);
+        assert_eq!(debugger.executed(), 100);
+
+        let reserved = parse_input("Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: 1,7,2,7");
+        let mut debugger = Debugger::new(reserved);
+        assert_eq!(
+            debugger.run(),
+            Stop::Error(VmError::InvalidOperand { pc: 2, operand: 7 })
+        );
+        assert_eq!(debugger.computer().reg, [1, 7, 0]);
+
+        let truncated = parse_input("Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: 5,4,0");
+        assert_eq!(truncated.output_with(1), Err(VmError::MissingOperand { pc: 2 }));
     }
 }
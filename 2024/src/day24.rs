@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
 use crate::utils::regex;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Operation {
     a: String,
     b: String,
@@ -13,14 +13,14 @@ struct Operation {
     out: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Op {
     And,
     Or,
     Xor,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParseResult {
     init_states: HashMap<String, bool>,
     logic: HashMap<String, Operation>,
@@ -90,50 +90,182 @@ pub fn parse_input(input: &str) -> ParseResult {
     ParseResult { init_states, logic }
 }
 
-fn calculate_state(input: &ParseResult, states: &mut HashMap<String, bool>, op: &Operation) -> bool {
-    let a = match states.get(&op.a) {
-        Some(value) => *value,
-        None => calculate_state(input, states, &input.logic[&op.a]),
-    };
+impl ParseResult {
+    /// Number of bits of the x and y inputs
+    fn bits(&self) -> usize {
+        self.init_states.keys().filter(|wire| wire.starts_with('x')).count()
+    }
 
-    let b = match states.get(&op.b) {
-        Some(value) => *value,
-        None => calculate_state(input, states, &input.logic[&op.b]),
-    };
+    /// Value of `wire`, `None` if it depends on itself (possible after swapping outputs)
+    fn wire_value(
+        &self,
+        wire: &str,
+        states: &mut HashMap<String, bool>,
+        visiting: &mut HashSet<String>,
+    ) -> Option<bool> {
+        if let Some(value) = states.get(wire) {
+            return Some(*value);
+        }
 
-    let new_state = op.apply(a, b);
-    states.insert(op.out.clone(), new_state);
+        let op = self.logic.get(wire)?;
+        if !visiting.insert(wire.to_string()) {
+            return None;
+        }
 
-    new_state
-}
+        let a = self.wire_value(&op.a, states, visiting)?;
+        let b = self.wire_value(&op.b, states, visiting)?;
+        visiting.remove(wire);
 
-#[aoc(day24, part1)]
-pub fn part1(input: &ParseResult) -> u64 {
-    let gates = input
-        .logic
-        .keys()
-        .filter(|key| key.starts_with('z'))
-        .sorted()
-        .collect::<Vec<_>>();
-
-    let mut states = input.init_states.clone();
-    let mut result = 0_u64;
-
-    for (pos, gate) in gates.iter().enumerate() {
-        let op = &input.logic[*gate];
-        calculate_state(input, &mut states, op);
-
-        if states[*gate] {
-            result |= 1 << (pos);
+        let new_state = op.apply(a, b);
+        states.insert(wire.to_string(), new_state);
+        Some(new_state)
+    }
+
+    fn output(&self, states: &mut HashMap<String, bool>) -> Option<u64> {
+        let gates = self.logic.keys().filter(|key| key.starts_with('z')).sorted();
+
+        let mut result = 0_u64;
+        for (pos, gate) in gates.enumerate() {
+            if self.wire_value(gate, states, &mut HashSet::new())? {
+                result |= 1 << pos;
+            }
+        }
+
+        Some(result)
+    }
+
+    /// Run the circuit with the given inputs instead of the initial states
+    pub fn evaluate(&self, x: u64, y: u64) -> Option<u64> {
+        let mut states = (0..self.bits())
+            .flat_map(|bit| {
+                [
+                    (format!("x{bit:02}"), x >> bit & 1 == 1),
+                    (format!("y{bit:02}"), y >> bit & 1 == 1),
+                ]
+            })
+            .collect();
+
+        self.output(&mut states)
+    }
+
+    /// Checks additions which exercise every bit and every carry
+    pub fn adds_correctly(&self) -> bool {
+        let bits = self.bits();
+        let mask = (1 << bits) - 1;
+
+        let single_bits = (0..bits).flat_map(|bit| [(1 << bit, 0), (0, 1 << bit), (1 << bit, 1 << bit)]);
+        let carry_chains = [
+            (mask, 1),
+            (1, mask),
+            (mask, mask),
+            (0x5555_5555_5555 & mask, 0x3333_3333_3333 & mask),
+        ];
+
+        single_bits
+            .chain(carry_chains)
+            .all(|(x, y)| self.evaluate(x, y) == Some(x + y))
+    }
+
+    /// Circuit with the outputs of the gates driving `a` and `b` exchanged
+    pub fn with_swapped(&self, a: &str, b: &str) -> ParseResult {
+        let mut swapped = self.clone();
+
+        let mut gate_a = swapped.logic.remove(a).unwrap();
+        let mut gate_b = swapped.logic.remove(b).unwrap();
+        gate_a.out = b.to_string();
+        gate_b.out = a.to_string();
+        swapped.logic.insert(b.to_string(), gate_a);
+        swapped.logic.insert(a.to_string(), gate_b);
+
+        swapped
+    }
+
+    /// Gate outputs which do not fit into a ripple-carry adder.
+    ///
+    /// Bit `i` of the adder is `z = (x ^ y) ^ carry` and `carry' = (x & y) | ((x ^ y) & carry)`,
+    /// bit 0 has no incoming carry and the last carry is the highest z.
+    pub fn suspicious_wires(&self) -> Vec<String> {
+        let highest_z = format!("z{:02}", self.bits());
+        let is_input = |wire: &str| wire.starts_with('x') || wire.starts_with('y');
+        let consumers = |wire: &str| {
+            self.logic
+                .values()
+                .filter(|op| op.a == wire || op.b == wire)
+                .map(|op| op.op)
+                .collect::<Vec<_>>()
+        };
+
+        let mut suspicious = BTreeSet::new();
+        for op in self.logic.values() {
+            let first_bit = [&op.a, &op.b].iter().any(|wire| *wire == "x00" || *wire == "y00");
+            let from_inputs = is_input(&op.a) && is_input(&op.b);
+
+            let fits = match op.op {
+                // Sum bits and the half adders feeding them
+                Op::Xor if from_inputs => first_bit || consumers(&op.out).contains(&Op::Xor),
+                Op::Xor => op.out.starts_with('z') && op.out != highest_z,
+                // Carries are combined by the OR, except the one of the first bit
+                Op::And => !op.out.starts_with('z') && (first_bit || consumers(&op.out).iter().all(|op| *op == Op::Or)),
+                Op::Or => !op.out.starts_with('z') || op.out == highest_z,
+            };
+
+            if !fits {
+                suspicious.insert(op.out.clone());
+            }
         }
+
+        suspicious.into_iter().collect()
     }
 
-    result
+    /// Pairs of swapped gate outputs, found by trying all pairings of the suspicious wires
+    pub fn find_swaps(&self) -> Option<Vec<(String, String)>> {
+        fn pair_up(circuit: &ParseResult, wires: &[String], pairs: &mut Vec<(String, String)>) -> bool {
+            let Some((first, rest)) = wires.split_first() else {
+                return circuit.adds_correctly();
+            };
+
+            for (idx, second) in rest.iter().enumerate() {
+                let remaining = rest
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != idx)
+                    .map(|(_, wire)| wire.clone());
+
+                pairs.push((first.clone(), second.clone()));
+                if pair_up(
+                    &circuit.with_swapped(first, second),
+                    &remaining.collect::<Vec<_>>(),
+                    pairs,
+                ) {
+                    return true;
+                }
+                pairs.pop();
+            }
+
+            false
+        }
+
+        let suspicious = self.suspicious_wires();
+        let mut pairs = vec![];
+
+        if suspicious.len().is_multiple_of(2) && pair_up(self, &suspicious, &mut pairs) {
+            Some(pairs)
+        } else {
+            None
+        }
+    }
+}
+
+#[aoc(day24, part1)]
+pub fn part1(input: &ParseResult) -> u64 {
+    input.output(&mut input.init_states.clone()).unwrap()
 }
 
 #[aoc(day24, part2)]
-pub fn part2(input: &ParseResult) -> isize {
-    todo!()
+pub fn part2(input: &ParseResult) -> String {
+    let swaps = input.find_swaps().expect("No swaps found which fix the adder");
+
+    swaps.into_iter().flat_map(|(a, b)| [a, b]).sorted().join(",")
 }
 
 #[cfg(test)]
@@ -155,15 +287,69 @@ mod tests {
         assert_eq!(part1(&input), 51107420031718);
     }
 
-    //#[test]
-    fn example_part2() {
-        let input = parse_input(EXAMPLE);
-        assert_eq!(part2(&input), todo!());
+    /// Ripple-carry adder as described in `suspicious_wires`, with the outputs of `swaps` exchanged
+    fn ripple_carry_adder(bits: usize, x: u64, y: u64, swaps: &[(&str, &str)]) -> String {
+        let carry = |bit: usize| {
+            if bit == bits - 1 {
+                format!("z{bits:02}")
+            } else {
+                format!("r{bit:02}")
+            }
+        };
+
+        let mut lines = (0..bits)
+            .map(|bit| format!("x{bit:02}: {}", x >> bit & 1))
+            .chain((0..bits).map(|bit| format!("y{bit:02}: {}", y >> bit & 1)))
+            .collect::<Vec<_>>();
+        lines.push(String::new());
+
+        lines.push(format!("x00 XOR y00 -> z00\nx00 AND y00 -> {}", carry(0)));
+        for bit in 1..bits {
+            lines.push(format!("x{bit:02} XOR y{bit:02} -> s{bit:02}"));
+            lines.push(format!("y{bit:02} AND x{bit:02} -> c{bit:02}"));
+            lines.push(format!("s{bit:02} XOR {} -> z{bit:02}", carry(bit - 1)));
+            lines.push(format!("{} AND s{bit:02} -> t{bit:02}", carry(bit - 1)));
+            lines.push(format!("c{bit:02} OR t{bit:02} -> {}", carry(bit)));
+        }
+
+        let mut circuit = lines.join("\n");
+        for (a, b) in swaps {
+            circuit = circuit
+                .replace(&format!("-> {a}"), "-> SWAP")
+                .replace(&format!("-> {b}"), &format!("-> {a}"))
+                .replace("-> SWAP", &format!("-> {b}"));
+        }
+        circuit
     }
 
-    //#[test]
-    fn input_part2() {
-        let input = parse_input(INPUT);
-        assert_eq!(part2(&input), todo!());
+    #[test]
+    fn evaluate() {
+        let input = parse_input(&ripple_carry_adder(12, 1234, 3000, &[]));
+
+        assert_eq!(part1(&input), 4234);
+        assert_eq!(input.evaluate(4095, 4095), Some(8190));
+        assert!(input.adds_correctly());
+        assert!(input.suspicious_wires().is_empty());
+    }
+
+    #[test]
+    fn swapped_wires() {
+        let input = parse_input(&ripple_carry_adder(
+            12,
+            1,
+            2,
+            &[("s03", "c03"), ("z07", "t07"), ("z10", "r09")],
+        ));
+
+        assert!(!input.adds_correctly());
+        assert_eq!(input.suspicious_wires(), ["c03", "r09", "s03", "t07", "z07", "z10"]);
+        assert_eq!(part2(&input), "c03,r09,s03,t07,z07,z10");
+
+        let fixed = input
+            .find_swaps()
+            .unwrap()
+            .iter()
+            .fold(input.clone(), |circuit, (a, b)| circuit.with_swapped(a, b));
+        assert!(fixed.adds_correctly());
     }
 }
@@ -27,7 +27,7 @@ pub mod day13;
 pub mod day14;
 // pub mod day15;
 pub mod day16;
-// pub mod day17;
+pub mod day17;
 pub mod day18;
 pub mod day19;
 pub mod day20;
@@ -0,0 +1,481 @@
+use std::collections::{HashMap, HashSet};
+
+/// Position in `N` dimensions, e.g. `[x, y]` or `[x, y, z, w]`
+pub type Pos<const N: usize> = [i32; N];
+
+/// Cells whose state is used to compute the next state of a cell.
+///
+/// The order of the neighbors is passed on to the rule, so rules like the
+/// image enhancement of 2021 day20 can depend on it.
+pub trait Neighborhood<const N: usize> {
+    fn neighbors(&self, pos: Pos<N>, out: &mut Vec<Pos<N>>);
+}
+
+/// Neighborhood given by a fixed list of offsets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offsets<const N: usize>(pub Vec<Pos<N>>);
+
+impl<const N: usize> Offsets<N> {
+    /// All cells at most one step away in every dimension, without the cell itself
+    pub fn moore() -> Self {
+        Self(Self::cube().0.into_iter().filter(|offset| *offset != [0; N]).collect())
+    }
+
+    /// The cell itself and all cells of `moore()`, in row-major order (last dimension first)
+    pub fn cube() -> Self {
+        let mut offsets = vec![[0; N]];
+
+        for dim in (0..N).rev() {
+            offsets = offsets
+                .into_iter()
+                .flat_map(|offset| {
+                    [-1, 0, 1].map(|delta| {
+                        let mut offset = offset;
+                        offset[dim] = delta;
+                        offset
+                    })
+                })
+                .collect();
+        }
+
+        // Sort by the last dimension first, e.g. by row then column for [x, y]
+        offsets.sort_by_key(|offset| offset.iter().rev().copied().collect::<Vec<_>>());
+        Self(offsets)
+    }
+
+    /// Cells sharing a face with the cell
+    pub fn von_neumann() -> Self {
+        Self(
+            Self::moore()
+                .0
+                .into_iter()
+                .filter(|offset| offset.iter().map(|delta| delta.abs()).sum::<i32>() == 1)
+                .collect(),
+        )
+    }
+}
+
+impl Offsets<2> {
+    /// Hexagonal neighborhood in axial coordinates `[q, r]`
+    pub fn hex() -> Self {
+        Self(vec![[1, 0], [-1, 0], [0, -1], [1, -1], [0, 1], [-1, 1]])
+    }
+}
+
+impl<const N: usize> Neighborhood<N> for Offsets<N> {
+    fn neighbors(&self, pos: Pos<N>, out: &mut Vec<Pos<N>>) {
+        out.extend(
+            self.0
+                .iter()
+                .map(|offset| std::array::from_fn(|dim| pos[dim] + offset[dim])),
+        );
+    }
+}
+
+/// Any function filling in the neighbors, e.g. precomputed line of sight neighbors
+impl<const N: usize, F: Fn(Pos<N>, &mut Vec<Pos<N>>)> Neighborhood<N> for F {
+    fn neighbors(&self, pos: Pos<N>, out: &mut Vec<Pos<N>>) {
+        self(pos, out);
+    }
+}
+
+/// How the cells of an automaton are stored, cells not stored have the background state
+pub trait Storage<const N: usize, S> {
+    fn get(&self, pos: Pos<N>) -> Option<S>;
+
+    fn set(&mut self, pos: Pos<N>, state: S, background: S);
+
+    /// Positions which have to be computed for the next generation and the (empty) storage for it
+    fn next_generation(&self, neighborhood: &impl Neighborhood<N>, background: S) -> (Vec<Pos<N>>, Self);
+
+    /// All stored cells, may include cells with the background state
+    fn cells(&self) -> Box<dyn Iterator<Item = (Pos<N>, S)> + '_>;
+}
+
+/// Only cells differing from the background are stored, allows unbounded growth.
+///
+/// Assumes a symmetric neighborhood: if `a` is a neighbor of `b`, `b` is a neighbor of `a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sparse<const N: usize, S> {
+    cells: HashMap<Pos<N>, S>,
+}
+
+impl<const N: usize, S: Copy + Eq> Sparse<N, S> {
+    pub fn new(cells: impl IntoIterator<Item = (Pos<N>, S)>) -> Self {
+        Self {
+            cells: cells.into_iter().collect(),
+        }
+    }
+}
+
+impl<const N: usize, S: Copy + Eq> Storage<N, S> for Sparse<N, S> {
+    fn get(&self, pos: Pos<N>) -> Option<S> {
+        self.cells.get(&pos).copied()
+    }
+
+    fn set(&mut self, pos: Pos<N>, state: S, background: S) {
+        if state == background {
+            self.cells.remove(&pos);
+        } else {
+            self.cells.insert(pos, state);
+        }
+    }
+
+    fn next_generation(&self, neighborhood: &impl Neighborhood<N>, _background: S) -> (Vec<Pos<N>>, Self) {
+        let mut positions = HashSet::new();
+        let mut neighbors = vec![];
+
+        for pos in self.cells.keys() {
+            positions.insert(*pos);
+
+            neighbors.clear();
+            neighborhood.neighbors(*pos, &mut neighbors);
+            positions.extend(neighbors.iter().copied());
+        }
+
+        (
+            positions.into_iter().collect(),
+            Self {
+                cells: HashMap::with_capacity(self.cells.len()),
+            },
+        )
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (Pos<N>, S)> + '_> {
+        Box::new(self.cells.iter().map(|(pos, state)| (*pos, *state)))
+    }
+}
+
+/// All cells inside a box are stored, the box can grow by `margin` cells per generation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dense<const N: usize, S> {
+    min: Pos<N>,
+    size: [usize; N],
+    margin: usize,
+    cells: Vec<S>,
+}
+
+impl<const N: usize, S: Copy> Dense<N, S> {
+    pub fn new(min: Pos<N>, size: [usize; N], fill: S) -> Self {
+        Self {
+            min,
+            size,
+            margin: 0,
+            cells: vec![fill; size.iter().product()],
+        }
+    }
+
+    /// Grow the box by `margin` cells in every direction for each generation,
+    /// needed when cells outside can change (e.g. growing patterns)
+    pub fn growing(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn min(&self) -> Pos<N> {
+        self.min
+    }
+
+    pub fn size(&self) -> [usize; N] {
+        self.size
+    }
+
+    fn index(&self, pos: Pos<N>) -> Option<usize> {
+        let mut index = 0;
+
+        for dim in (0..N).rev() {
+            let offset = usize::try_from(pos[dim] - self.min[dim]).ok()?;
+            if offset >= self.size[dim] {
+                return None;
+            }
+            index = index * self.size[dim] + offset;
+        }
+
+        Some(index)
+    }
+
+    fn position(&self, mut index: usize) -> Pos<N> {
+        let mut pos = self.min;
+
+        for (coord, size) in pos.iter_mut().zip(self.size) {
+            *coord += (index % size) as i32;
+            index /= size;
+        }
+
+        pos
+    }
+}
+
+impl<S: Copy> Dense<2, S> {
+    /// Rows of cells, position `[x, y]` starting at `[0, 0]`
+    pub fn from_rows(rows: Vec<Vec<S>>) -> Self {
+        let size = [rows.first().map_or(0, Vec::len), rows.len()];
+        assert!(
+            rows.iter().all(|row| row.len() == size[0]),
+            "all rows must have the same length"
+        );
+
+        Self {
+            min: [0, 0],
+            size,
+            margin: 0,
+            cells: rows.into_iter().flatten().collect(),
+        }
+    }
+}
+
+impl<const N: usize, S: Copy> Storage<N, S> for Dense<N, S> {
+    fn get(&self, pos: Pos<N>) -> Option<S> {
+        self.index(pos).map(|index| self.cells[index])
+    }
+
+    fn set(&mut self, pos: Pos<N>, state: S, _background: S) {
+        let index = self.index(pos).expect("position outside of the dense storage");
+        self.cells[index] = state;
+    }
+
+    fn next_generation(&self, _neighborhood: &impl Neighborhood<N>, background: S) -> (Vec<Pos<N>>, Self) {
+        let margin = self.margin as i32;
+        let next = Self::new(
+            self.min.map(|min| min - margin),
+            self.size.map(|size| size + 2 * self.margin),
+            background,
+        )
+        .growing(self.margin);
+
+        ((0..next.cells.len()).map(|index| next.position(index)).collect(), next)
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (Pos<N>, S)> + '_> {
+        Box::new(
+            self.cells
+                .iter()
+                .enumerate()
+                .map(|(index, state)| (self.position(index), *state)),
+        )
+    }
+}
+
+/// Cellular automaton with an infinite background.
+///
+/// Every cell not in the storage has the `background` state, the background
+/// itself evolves by applying the rule to a cell surrounded by background
+/// (e.g. the flashing infinite image of 2021 day20).
+///
+/// Rules are synchronous and local: the next state of a cell only depends on the
+/// current states of its neighbors. Chain reactions within one step (the octopus
+/// flashes of 2021 day11) or moves resolved against other cells' moves in a rotating
+/// order (the elves of 2022 day23) do not fit this model.
+pub struct Automaton<const N: usize, S, G, H, R> {
+    cells: G,
+    neighborhood: H,
+    rule: R,
+    background: S,
+    generation: usize,
+}
+
+impl<const N: usize, S, G, H, R> Automaton<N, S, G, H, R>
+where
+    S: Copy + Eq,
+    G: Storage<N, S>,
+    H: Neighborhood<N>,
+    R: Fn(S, &[S]) -> S,
+{
+    /// `rule` gets the current state of a cell and the states of its neighbors (in neighborhood order)
+    pub fn new(cells: G, neighborhood: H, rule: R, background: S) -> Self {
+        Self {
+            cells,
+            neighborhood,
+            rule,
+            background,
+            generation: 0,
+        }
+    }
+
+    pub fn get(&self, pos: Pos<N>) -> S {
+        self.cells.get(pos).unwrap_or(self.background)
+    }
+
+    pub fn background(&self) -> S {
+        self.background
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn storage(&self) -> &G {
+        &self.cells
+    }
+
+    /// Stored cells which differ from the background
+    pub fn cells(&self) -> impl Iterator<Item = (Pos<N>, S)> + '_ {
+        self.cells.cells().filter(|(_, state)| *state != self.background)
+    }
+
+    /// Number of cells with `state`, `None` if the background has this state (infinitely many)
+    pub fn count(&self, state: S) -> Option<usize> {
+        if state == self.background {
+            return None;
+        }
+        Some(self.cells.cells().filter(|(_, cell)| *cell == state).count())
+    }
+
+    /// Compute the next generation, returns the number of cells that changed
+    /// (not counting the background)
+    pub fn step(&mut self) -> usize {
+        let mut neighbors = vec![];
+        let mut states = vec![];
+
+        self.neighborhood.neighbors([0; N], &mut neighbors);
+        let next_background = (self.rule)(self.background, &vec![self.background; neighbors.len()]);
+
+        let (positions, mut next) = self.cells.next_generation(&self.neighborhood, next_background);
+        let mut changed = 0;
+
+        for pos in positions {
+            neighbors.clear();
+            self.neighborhood.neighbors(pos, &mut neighbors);
+
+            states.clear();
+            states.extend(neighbors.iter().map(|neighbor| self.get(*neighbor)));
+
+            let current = self.get(pos);
+            let state = (self.rule)(current, &states);
+            if state != current {
+                changed += 1;
+            }
+
+            next.set(pos, state, next_background);
+        }
+
+        self.cells = next;
+        self.background = next_background;
+        self.generation += 1;
+        changed
+    }
+
+    pub fn run(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    /// Step until neither a cell nor the background changes anymore, returns the number of steps taken
+    /// (including the last one without changes)
+    pub fn run_until_stable(&mut self) -> usize {
+        let start = self.generation;
+
+        loop {
+            let background = self.background;
+            if self.step() == 0 && background == self.background {
+                return self.generation - start;
+            }
+        }
+    }
+}
+
+/// Rule for boolean automatons based on the number of alive neighbors, e.g. `life_like(&[3], &[2, 3])`
+pub fn life_like(birth: &'static [usize], survive: &'static [usize]) -> impl Fn(bool, &[bool]) -> bool {
+    move |alive, neighbors| {
+        let count = neighbors.iter().filter(|neighbor| **neighbor).count();
+
+        if alive {
+            survive.contains(&count)
+        } else {
+            birth.contains(&count)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets() {
+        assert_eq!(Offsets::<2>::moore().0.len(), 8);
+        assert_eq!(Offsets::<4>::moore().0.len(), 80);
+        assert_eq!(Offsets::<3>::von_neumann().0.len(), 6);
+        assert_eq!(Offsets::<2>::cube().0[..4], [[-1, -1], [0, -1], [1, -1], [-1, 0]]);
+    }
+
+    #[test]
+    fn sparse_and_dense_agree() {
+        // Glider
+        let alive = [[1, 0], [2, 1], [0, 2], [1, 2], [2, 2]];
+
+        let mut sparse = Automaton::new(
+            Sparse::new(alive.map(|pos| (pos, true))),
+            Offsets::moore(),
+            life_like(&[3], &[2, 3]),
+            false,
+        );
+
+        let mut dense = Dense::new([-2, -2], [20, 20], false);
+        for pos in alive {
+            dense.set(pos, true, false);
+        }
+        let mut dense = Automaton::new(dense, Offsets::moore(), life_like(&[3], &[2, 3]), false);
+
+        sparse.run(8);
+        dense.run(8);
+
+        let mut sparse_cells = sparse.cells().map(|(pos, _)| pos).collect::<Vec<_>>();
+        let mut dense_cells = dense.cells().map(|(pos, _)| pos).collect::<Vec<_>>();
+        sparse_cells.sort_unstable();
+        dense_cells.sort_unstable();
+
+        assert_eq!(sparse_cells, dense_cells);
+        assert_eq!(sparse_cells, [[2, 4], [3, 2], [3, 4], [4, 3], [4, 4]]);
+        assert_eq!(sparse.count(true), Some(5));
+        assert_eq!(sparse.count(false), None);
+    }
+
+    #[test]
+    fn conway_cubes() {
+        let initial = [[1, 0, 0], [2, 1, 0], [0, 2, 0], [1, 2, 0], [2, 2, 0]];
+        let mut cubes = Automaton::new(
+            Sparse::new(initial.map(|pos| (pos, true))),
+            Offsets::moore(),
+            life_like(&[3], &[2, 3]),
+            false,
+        );
+
+        cubes.run(6);
+        assert_eq!(cubes.count(true), Some(112));
+    }
+
+    #[test]
+    fn flipping_background() {
+        // Every cell inverts, so the background flips every generation
+        let mut blink = Automaton::new(
+            Dense::new([0], [3], false),
+            Offsets::<1>::moore(),
+            |state, _| !state,
+            false,
+        );
+
+        blink.step();
+        assert!(blink.background());
+        assert_eq!(blink.count(false), Some(0));
+
+        blink.step();
+        assert!(!blink.background());
+        assert_eq!(blink.count(true), Some(0));
+    }
+
+    #[test]
+    fn stabilizes() {
+        // A block is a still life
+        let mut block = Automaton::new(
+            Sparse::new([[0, 0], [1, 0], [0, 1], [1, 1]].map(|pos| (pos, true))),
+            Offsets::moore(),
+            life_like(&[3], &[2, 3]),
+            false,
+        );
+
+        assert_eq!(block.run_until_stable(), 1);
+        assert_eq!(block.count(true), Some(4));
+    }
+}
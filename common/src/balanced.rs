@@ -0,0 +1,268 @@
+use std::fmt::{self, Display, Formatter};
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::Add;
+use std::str::FromStr;
+
+/// Digit alphabet of a balanced numeral system, ordered from the lowest to the highest digit value.
+///
+/// The base is the number of digits and has to be odd, the middle digit is zero.
+pub trait Digits {
+    const ALPHABET: &'static str;
+}
+
+/// Base 5 with the digits `=` (-2), `-` (-1), `0`, `1` and `2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snafu;
+
+impl Digits for Snafu {
+    const ALPHABET: &'static str = "=-012";
+}
+
+/// Base 3 with the digits `-`, `0` and `+`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ternary;
+
+impl Digits for Ternary {
+    const ALPHABET: &'static str = "-0+";
+}
+
+/// Number in a balanced base, every digit is stored so there is no upper limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Balanced<D> {
+    /// Digit values, least significant first and without leading zeros
+    digits: Vec<i8>,
+    alphabet: PhantomData<D>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseBalancedError {
+    Empty,
+    InvalidDigit(char),
+}
+
+impl Display for ParseBalancedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBalancedError::Empty => write!(f, "number has no digits"),
+            ParseBalancedError::InvalidDigit(ch) => write!(f, "'{ch}' is not a digit"),
+        }
+    }
+}
+
+impl std::error::Error for ParseBalancedError {}
+
+impl<D: Digits> Balanced<D> {
+    fn base() -> i8 {
+        let base = D::ALPHABET.chars().count();
+        assert!(
+            base % 2 == 1 && base < 128,
+            "balanced bases need an odd number of digits"
+        );
+        base as i8
+    }
+
+    /// Highest digit value, the lowest one is its negation
+    fn max_digit() -> i8 {
+        Self::base() / 2
+    }
+
+    fn from_digits(mut digits: Vec<i8>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        Balanced {
+            digits,
+            alphabet: PhantomData,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::from_digits(vec![])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Value as native integer, `None` if it does not fit
+    pub fn to_i128(&self) -> Option<i128> {
+        let base = i128::from(Self::base());
+        self.digits.iter().rev().try_fold(0_i128, |nb, &digit| {
+            nb.checked_mul(base)?.checked_add(i128::from(digit))
+        })
+    }
+}
+
+impl<D: Digits> From<i64> for Balanced<D> {
+    fn from(mut value: i64) -> Self {
+        let base = i64::from(Self::base());
+        let max = i64::from(Self::max_digit());
+
+        let mut digits = vec![];
+        while value != 0 {
+            // Shift into 0..base, so the remainder maps to -max..=max
+            let digit = (value + max).rem_euclid(base) - max;
+            digits.push(digit as i8);
+            value = (value - digit) / base;
+        }
+
+        Self::from_digits(digits)
+    }
+}
+
+impl<D: Digits> FromStr for Balanced<D> {
+    type Err = ParseBalancedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseBalancedError::Empty);
+        }
+
+        let max = Self::max_digit();
+        let digits = s
+            .chars()
+            .rev()
+            .map(|ch| match D::ALPHABET.chars().position(|digit| digit == ch) {
+                Some(idx) => Ok(idx as i8 - max),
+                None => Err(ParseBalancedError::InvalidDigit(ch)),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self::from_digits(digits))
+    }
+}
+
+impl<D: Digits> Display for Balanced<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let max = Self::max_digit();
+        let alphabet = D::ALPHABET.chars().collect::<Vec<_>>();
+
+        if self.is_zero() {
+            return write!(f, "{}", alphabet[max as usize]);
+        }
+        for &digit in self.digits.iter().rev() {
+            write!(f, "{}", alphabet[(digit + max) as usize])?;
+        }
+        Ok(())
+    }
+}
+
+/// Digit by digit with carry, like written addition
+impl<D: Digits> Add for &Balanced<D> {
+    type Output = Balanced<D>;
+
+    fn add(self, other: Self) -> Balanced<D> {
+        let base = Balanced::<D>::base();
+        let max = Balanced::<D>::max_digit();
+
+        let len = self.digits.len().max(other.digits.len());
+        let mut digits = Vec::with_capacity(len + 1);
+        let mut carry = 0;
+
+        for idx in 0..len {
+            let a = self.digits.get(idx).copied().unwrap_or(0);
+            let b = other.digits.get(idx).copied().unwrap_or(0);
+
+            // Both digits and the carry are in -max..=max, so one correction is always enough
+            let mut sum = a + b + carry;
+            carry = 0;
+            if sum > max {
+                sum -= base;
+                carry = 1;
+            } else if sum < -max {
+                sum += base;
+                carry = -1;
+            }
+            digits.push(sum);
+        }
+        digits.push(carry);
+
+        Balanced::from_digits(digits)
+    }
+}
+
+impl<D: Digits> Add for Balanced<D> {
+    type Output = Balanced<D>;
+
+    fn add(self, other: Self) -> Balanced<D> {
+        &self + &other
+    }
+}
+
+impl<'a, D: Digits> Sum<&'a Balanced<D>> for Balanced<D> {
+    fn sum<I: Iterator<Item = &'a Balanced<D>>>(iter: I) -> Self {
+        iter.fold(Balanced::zero(), |sum, nb| &sum + nb)
+    }
+}
+
+impl<D: Digits> Sum for Balanced<D> {
+    fn sum<I: Iterator<Item = Balanced<D>>>(iter: I) -> Self {
+        iter.fold(Balanced::zero(), |sum, nb| &sum + &nb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snafu_conversion() {
+        let table = [
+            (0, "0"),
+            (1, "1"),
+            (3, "1="),
+            (8, "2="),
+            (10, "20"),
+            (15, "1=0"),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314_159_265, "1121-1110-1=0"),
+            (-7, "-="),
+        ];
+
+        for (value, snafu) in table {
+            let nb = Balanced::<Snafu>::from(value);
+            assert_eq!(nb.to_string(), snafu);
+            assert_eq!(snafu.parse::<Balanced<Snafu>>().unwrap(), nb);
+            assert_eq!(nb.to_i128(), Some(i128::from(value)));
+        }
+
+        assert_eq!("00012".parse::<Balanced<Snafu>>().unwrap().to_string(), "12");
+        assert_eq!("".parse::<Balanced<Snafu>>(), Err(ParseBalancedError::Empty));
+        assert_eq!(
+            "13".parse::<Balanced<Snafu>>(),
+            Err(ParseBalancedError::InvalidDigit('3'))
+        );
+    }
+
+    #[test]
+    fn addition() {
+        for a in -200..200 {
+            for b in [-1000, -13, -1, 0, 2, 7, 124, 3000] {
+                let sum = Balanced::<Snafu>::from(a) + Balanced::from(b);
+                assert_eq!(sum.to_i128(), Some(i128::from(a + b)), "{a} + {b}");
+
+                let sum = Balanced::<Ternary>::from(a) + Balanced::from(b);
+                assert_eq!(sum.to_i128(), Some(i128::from(a + b)), "{a} + {b}");
+            }
+        }
+
+        assert_eq!((Balanced::<Ternary>::from(5) + Balanced::from(-5)).to_string(), "0");
+        assert_eq!(Balanced::<Ternary>::from(8).to_string(), "+0-");
+    }
+
+    #[test]
+    fn no_overflow() {
+        let large = format!("1{}", "0".repeat(60)).parse::<Balanced<Snafu>>().unwrap();
+        assert_eq!(large.to_i128(), None);
+
+        let sum = [&large, &large, &large].into_iter().sum::<Balanced<Snafu>>();
+        assert_eq!(sum.to_string(), format!("1={}", "0".repeat(60)));
+
+        let difference = &sum + &format!("-{}", "0".repeat(60)).parse().unwrap();
+        assert_eq!(difference.to_string(), format!("2{}", "0".repeat(60)));
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    fmt,
+    ops::{BitAnd, BitOr, BitXor, Not},
+};
+
+const BITS: usize = u64::BITS as usize;
+
+/// 2D grid of booleans packed into `u64` words, one bit per cell.
+///
+/// Every row starts at a word boundary, so row-wise operations and shifts can
+/// work on whole words. Bits outside of `width` are always kept at zero.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    data: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(BITS);
+
+        Self {
+            width,
+            height,
+            words_per_row,
+            data: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, cell: impl Fn(usize, usize) -> bool) -> Self {
+        let mut grid = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if cell(x, y) {
+                    grid.set(x, y, true);
+                }
+            }
+        }
+        grid
+    }
+
+    /// All rows must have the same length
+    pub fn from_rows<R: AsRef<[bool]>>(rows: &[R]) -> Self {
+        let width = rows.first().map_or(0, |row| row.as_ref().len());
+        assert!(
+            rows.iter().all(|row| row.as_ref().len() == width),
+            "all rows must have the same length"
+        );
+
+        Self::from_fn(width, rows.len(), |x, y| rows[y].as_ref()[x])
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        assert!(x < self.width && y < self.height, "({x}, {y}) is outside of the grid");
+        self.data[y * self.words_per_row + x / BITS] & (1 << (x % BITS)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        assert!(x < self.width && y < self.height, "({x}, {y}) is outside of the grid");
+
+        let word = &mut self.data[y * self.words_per_row + x / BITS];
+        if value {
+            *word |= 1 << (x % BITS);
+        } else {
+            *word &= !(1 << (x % BITS));
+        }
+    }
+
+    /// Raw words of a single row, cell `x` is bit `x % 64` of word `x / 64`
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.data[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.data.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn any(&self) -> bool {
+        self.data.iter().any(|word| *word != 0)
+    }
+
+    /// Coordinates `(x, y)` of all set cells, row by row
+    pub fn iter_ones(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| {
+            self.row(y).iter().enumerate().flat_map(move |(idx, word)| {
+                let mut word = *word;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        return None;
+                    }
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some((idx * BITS + bit, y))
+                })
+            })
+        })
+    }
+
+    /// Cells set in `self` but not in `other`
+    pub fn and_not(&self, other: &BitGrid) -> BitGrid {
+        self.zip_words(other, |a, b| a & !b)
+    }
+
+    /// Move all cells by `dx` columns and `dy` rows, cells moved outside are lost and free cells are zero
+    pub fn shifted(&self, dx: isize, dy: isize) -> BitGrid {
+        let mut result = BitGrid::new(self.width, self.height);
+
+        for y in 0..self.height {
+            let Some(src_y) = y.checked_add_signed(-dy).filter(|src_y| *src_y < self.height) else {
+                continue;
+            };
+
+            let start = y * self.words_per_row;
+            shift_row(self.row(src_y), &mut result.data[start..start + self.words_per_row], dx);
+        }
+
+        result.mask_padding();
+        result
+    }
+
+    /// Move all cells by `dx` columns and `dy` rows, wrapping around at the edges
+    pub fn rotated(&self, dx: isize, dy: isize) -> BitGrid {
+        if self.width == 0 || self.height == 0 {
+            return self.clone();
+        }
+
+        let dx = dx.rem_euclid(self.width as isize);
+        let dy = dy.rem_euclid(self.height as isize);
+        let width = self.width as isize;
+        let height = self.height as isize;
+
+        &(&self.shifted(dx, dy) | &self.shifted(dx - width, dy))
+            | &(&self.shifted(dx, dy - height) | &self.shifted(dx - width, dy - height))
+    }
+
+    /// Number of set cells among the 8 surrounding cells, for every cell
+    pub fn neighbor_counts(&self) -> NeighborCounts {
+        self.neighbor_counts_with(&[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)])
+    }
+
+    /// Number of set cells at the given `(dx, dy)` offsets, for every cell (at most 15 offsets)
+    pub fn neighbor_counts_with(&self, offsets: &[(isize, isize)]) -> NeighborCounts {
+        assert!(offsets.len() < 1 << COUNT_PLANES, "at most 15 offsets are supported");
+
+        let mut counts = NeighborCounts {
+            planes: std::array::from_fn(|_| BitGrid::new(self.width, self.height)),
+        };
+
+        for (dx, dy) in offsets {
+            // The neighbor at (x + dx, y + dy) has to end up at (x, y)
+            counts.add(&self.shifted(-dx, -dy));
+        }
+
+        counts
+    }
+
+    fn zip_words(&self, other: &BitGrid, op: impl Fn(u64, u64) -> u64) -> BitGrid {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "grids must have the same size"
+        );
+
+        let mut result = self.clone();
+        for (word, other) in result.data.iter_mut().zip(&other.data) {
+            *word = op(*word, *other);
+        }
+        result
+    }
+
+    fn mask_padding(&mut self) {
+        let used_bits = self.width % BITS;
+        if used_bits == 0 {
+            return;
+        }
+
+        let mask = (1 << used_bits) - 1;
+        for row in self.data.chunks_mut(self.words_per_row) {
+            *row.last_mut().unwrap() &= mask;
+        }
+    }
+}
+
+/// Shift the cells of a single row by `dx` (towards higher x for positive values)
+fn shift_row(src: &[u64], dest: &mut [u64], dx: isize) {
+    let word_shift = dx.unsigned_abs() / BITS;
+    let bit_shift = dx.unsigned_abs() % BITS;
+    let word = |idx: Option<usize>| idx.and_then(|idx| src.get(idx)).copied().unwrap_or(0);
+
+    for (idx, dest) in dest.iter_mut().enumerate() {
+        *dest = if dx >= 0 {
+            let low = idx.checked_sub(word_shift);
+            let lower = low.and_then(|low| low.checked_sub(1));
+
+            if bit_shift == 0 {
+                word(low)
+            } else {
+                (word(low) << bit_shift) | (word(lower) >> (BITS - bit_shift))
+            }
+        } else {
+            let high = idx + word_shift;
+
+            if bit_shift == 0 {
+                word(Some(high))
+            } else {
+                (word(Some(high)) >> bit_shift) | (word(Some(high + 1)) << (BITS - bit_shift))
+            }
+        };
+    }
+}
+
+const COUNT_PLANES: usize = 4;
+
+/// Per cell counts stored bit-sliced: plane `n` holds bit `n` of every count
+#[derive(Clone, PartialEq, Eq)]
+pub struct NeighborCounts {
+    planes: [BitGrid; COUNT_PLANES],
+}
+
+impl NeighborCounts {
+    /// Add one to the count of every cell set in `grid` (word-wise ripple carry)
+    fn add(&mut self, grid: &BitGrid) {
+        for (idx, word) in grid.data.iter().enumerate() {
+            let mut carry = *word;
+            for plane in &mut self.planes {
+                let sum = plane.data[idx] ^ carry;
+                carry &= plane.data[idx];
+                plane.data[idx] = sum;
+            }
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.planes
+            .iter()
+            .enumerate()
+            .map(|(bit, plane)| u8::from(plane.get(x, y)) << bit)
+            .sum()
+    }
+
+    /// Cells whose count is exactly `count`
+    pub fn equal_to(&self, count: u8) -> BitGrid {
+        let mut result = !&BitGrid::new(self.planes[0].width, self.planes[0].height);
+
+        for (bit, plane) in self.planes.iter().enumerate() {
+            result = if count & (1 << bit) != 0 {
+                &result & plane
+            } else {
+                result.and_not(plane)
+            };
+        }
+
+        if usize::from(count) >> COUNT_PLANES != 0 {
+            result = BitGrid::new(result.width, result.height);
+        }
+        result
+    }
+
+    /// Cells whose count is smaller than `count`
+    pub fn less_than(&self, count: u8) -> BitGrid {
+        let empty = BitGrid::new(self.planes[0].width, self.planes[0].height);
+        (0..count).fold(empty, |result, value| &result | &self.equal_to(value))
+    }
+
+    /// Cells whose count is `count` or more
+    pub fn at_least(&self, count: u8) -> BitGrid {
+        !&self.less_than(count)
+    }
+}
+
+impl BitAnd for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitand(self, rhs: Self) -> BitGrid {
+        self.zip_words(rhs, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitor(self, rhs: Self) -> BitGrid {
+        self.zip_words(rhs, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitxor(self, rhs: Self) -> BitGrid {
+        self.zip_words(rhs, |a, b| a ^ b)
+    }
+}
+
+impl Not for &BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        let mut result = self.clone();
+        for word in &mut result.data {
+            *word = !*word;
+        }
+        result.mask_padding();
+        result
+    }
+}
+
+impl fmt::Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                write!(f, "{}", if self.get(x, y) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BitGrid {}x{}", self.width, self.height)?;
+        write!(f, "{self}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> BitGrid {
+        let rows = input
+            .lines()
+            .map(|line| line.chars().map(|ch| ch == '#').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        BitGrid::from_rows(&rows)
+    }
+
+    #[test]
+    fn shifts_across_words() {
+        let mut grid = BitGrid::new(130, 3);
+        grid.set(63, 0, true);
+        grid.set(0, 1, true);
+        grid.set(129, 2, true);
+
+        let shifted = grid.shifted(1, 0);
+        assert_eq!(shifted.iter_ones().collect::<Vec<_>>(), [(64, 0), (1, 1)]);
+
+        let shifted = grid.shifted(-64, -1);
+        assert_eq!(shifted.iter_ones().collect::<Vec<_>>(), [(65, 1)]);
+
+        let rotated = grid.rotated(1, 1);
+        assert_eq!(rotated.iter_ones().collect::<Vec<_>>(), [(0, 0), (64, 1), (1, 2)]);
+        assert_eq!(rotated.rotated(-1, -1), grid);
+    }
+
+    #[test]
+    fn neighbor_counts() {
+        let grid = parse("#.#\n.##\n###");
+        let counts = grid.neighbor_counts();
+
+        let expected = [[1, 4, 2], [4, 6, 4], [2, 4, 3]];
+        for (y, row) in expected.iter().enumerate() {
+            for (x, count) in row.iter().enumerate() {
+                assert_eq!(counts.get(x, y), *count, "count at ({x}, {y})");
+            }
+        }
+
+        assert_eq!(counts.equal_to(4).count_ones(), 4);
+        assert_eq!(counts.less_than(3).count_ones(), 3);
+        assert_eq!(counts.at_least(6).iter_ones().collect::<Vec<_>>(), [(1, 1)]);
+    }
+
+    #[test]
+    fn game_of_life_glider() {
+        let mut grid = BitGrid::new(70, 8);
+        for (x, y) in [(62, 0), (63, 1), (61, 2), (62, 2), (63, 2)] {
+            grid.set(x, y, true);
+        }
+
+        let start = grid.clone();
+        for _ in 0..4 {
+            let counts = grid.neighbor_counts();
+            grid = &counts.equal_to(3) | &(&grid & &counts.equal_to(2));
+        }
+
+        assert_eq!(grid, start.shifted(1, 1));
+    }
+}
@@ -0,0 +1,553 @@
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    hash::Hash,
+    ops::Range,
+};
+
+pub type NodeId = usize;
+
+/// Directed graph with interned node keys.
+///
+/// Every distinct key (e.g. a `String` name) is mapped to a dense `NodeId` the
+/// first time it is seen, so the algorithms below can work on plain indices.
+#[derive(Debug, Clone)]
+pub struct Graph<N = String, E = ()> {
+    keys: Vec<N>,
+    lookup: HashMap<N, NodeId>,
+    outgoing: Vec<Vec<(NodeId, E)>>,
+    incoming: Vec<Vec<NodeId>>,
+}
+
+/// A cycle found while sorting, in edge order (the last node points to the first)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle(pub Vec<NodeId>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph contains a cycle of length {}", self.0.len())
+    }
+}
+
+impl std::error::Error for Cycle {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinCut {
+    pub weight: u64,
+    /// Nodes on one side of the cut, all other nodes are on the other side
+    pub partition: Vec<NodeId>,
+}
+
+impl<N, E> Default for Graph<N, E> {
+    fn default() -> Self {
+        Self {
+            keys: vec![],
+            lookup: HashMap::new(),
+            outgoing: vec![],
+            incoming: vec![],
+        }
+    }
+}
+
+impl<N: Hash + Eq + Clone, E> Graph<N, E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the id for `key`, inserting a new node if it is unknown
+    pub fn add_node<Q>(&mut self, key: &Q) -> NodeId
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = N> + ?Sized,
+    {
+        if let Some(id) = self.lookup.get(key) {
+            return *id;
+        }
+
+        let id = self.keys.len();
+        self.keys.push(key.to_owned());
+        self.lookup.insert(key.to_owned(), id);
+        self.outgoing.push(vec![]);
+        self.incoming.push(vec![]);
+        id
+    }
+
+    pub fn add_weighted_edge<Q>(&mut self, from: &Q, to: &Q, weight: E) -> (NodeId, NodeId)
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = N> + ?Sized,
+    {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        self.add_edge_by_id(from, to, weight);
+        (from, to)
+    }
+
+    pub fn add_edge_by_id(&mut self, from: NodeId, to: NodeId, weight: E) {
+        self.outgoing[from].push((to, weight));
+        self.incoming[to].push(from);
+    }
+
+    pub fn id<Q>(&self, key: &Q) -> Option<NodeId>
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lookup.get(key).copied()
+    }
+
+    pub fn key(&self, id: NodeId) -> &N {
+        &self.keys[id]
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn nodes(&self) -> Range<NodeId> {
+        0..self.keys.len()
+    }
+
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.outgoing[id].iter().map(|(to, _)| *to)
+    }
+
+    pub fn edges(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &E)> + '_ {
+        self.outgoing[id].iter().map(|(to, weight)| (*to, weight))
+    }
+
+    pub fn predecessors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.incoming[id].iter().copied()
+    }
+
+    pub fn has_edge(&self, from: NodeId, to: NodeId) -> bool {
+        self.outgoing[from].iter().any(|(dest, _)| *dest == to)
+    }
+
+    /// Kahn's algorithm over the whole graph
+    pub fn topological_sort(&self) -> Result<Vec<NodeId>, Cycle> {
+        self.topological_sort_subset(&self.nodes().collect::<Vec<_>>())
+    }
+
+    /// Kahn's algorithm only considering the edges between `nodes`.
+    ///
+    /// Nodes without ordering constraints keep their relative input order.
+    pub fn topological_sort_subset(&self, nodes: &[NodeId]) -> Result<Vec<NodeId>, Cycle> {
+        let mut in_subset = vec![false; self.node_count()];
+        for node in nodes {
+            in_subset[*node] = true;
+        }
+
+        let mut in_degree = vec![0_usize; self.node_count()];
+        for &node in nodes {
+            in_degree[node] = self.predecessors(node).filter(|pred| in_subset[*pred]).count();
+        }
+
+        let mut queue = nodes
+            .iter()
+            .copied()
+            .filter(|node| in_degree[*node] == 0)
+            .collect::<VecDeque<_>>();
+        let mut sorted = Vec::with_capacity(nodes.len());
+
+        while let Some(node) = queue.pop_front() {
+            sorted.push(node);
+
+            for next in self.neighbors(node).filter(|next| in_subset[*next]) {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if sorted.len() == nodes.len() {
+            return Ok(sorted);
+        }
+
+        // Every node left still has a predecessor that is left, so walking
+        // backwards must eventually run into a node already seen
+        let remaining = |node: NodeId| in_subset[node] && in_degree[node] > 0;
+        let mut path = vec![nodes.iter().copied().find(|node| remaining(*node)).unwrap()];
+        let mut position = HashMap::from([(path[0], 0)]);
+
+        loop {
+            let pred = self
+                .predecessors(*path.last().unwrap())
+                .find(|pred| remaining(*pred))
+                .unwrap();
+
+            if let Some(&start) = position.get(&pred) {
+                let mut cycle = path.split_off(start);
+                cycle.reverse();
+                return Err(Cycle(cycle));
+            }
+
+            position.insert(pred, path.len());
+            path.push(pred);
+        }
+    }
+
+    /// Number of distinct paths from `from` to `to`, the graph must be acyclic
+    pub fn count_paths(&self, from: NodeId, to: NodeId) -> u64 {
+        self.count_paths_through(from, to, &[])
+    }
+
+    /// Number of distinct paths from `from` to `to` visiting every node in `must_visit`.
+    ///
+    /// The graph must be acyclic, at most 64 required nodes are supported.
+    pub fn count_paths_through(&self, from: NodeId, to: NodeId, must_visit: &[NodeId]) -> u64 {
+        assert!(must_visit.len() <= 64, "at most 64 required nodes are supported");
+
+        let mut required = vec![0_u64; self.node_count()];
+        for (idx, node) in must_visit.iter().enumerate() {
+            required[*node] |= 1 << idx;
+        }
+
+        let all_visited = must_visit.iter().fold(0, |mask, node| mask | required[*node]);
+        let mut cache = HashMap::new();
+
+        self.count_paths_cached(&mut cache, &required, all_visited, from, to, 0)
+    }
+
+    fn count_paths_cached(
+        &self,
+        cache: &mut HashMap<(NodeId, u64), u64>,
+        required: &[u64],
+        all_visited: u64,
+        node: NodeId,
+        end: NodeId,
+        visited: u64,
+    ) -> u64 {
+        let visited = visited | required[node];
+
+        if node == end {
+            return u64::from(visited == all_visited);
+        }
+
+        if let Some(cached_result) = cache.get(&(node, visited)) {
+            return *cached_result;
+        }
+
+        let mut total = 0;
+        for next in self.neighbors(node) {
+            total += self.count_paths_cached(cache, required, all_visited, next, end, visited);
+        }
+
+        cache.insert((node, visited), total);
+        total
+    }
+
+    /// Largest clique of the graph viewed as undirected (Bron–Kerbosch with pivoting), sorted by id
+    pub fn max_clique(&self) -> Vec<NodeId> {
+        let adjacency = self
+            .nodes()
+            .map(|node| {
+                self.neighbors(node)
+                    .chain(self.predecessors(node))
+                    .filter(|other| *other != node)
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut best = vec![];
+        bron_kerbosch(
+            &adjacency,
+            &mut vec![],
+            self.nodes().collect(),
+            HashSet::new(),
+            &mut best,
+        );
+
+        best.sort_unstable();
+        best
+    }
+
+    /// Global minimum cut of the graph viewed as undirected (Stoer–Wagner).
+    ///
+    /// The capacity between two nodes is the largest weight stored in either
+    /// direction, so edges added in both directions are only counted once.
+    /// Returns `None` for graphs with less than two nodes.
+    #[allow(clippy::needless_range_loop)]
+    pub fn min_cut_by(&self, weight: impl Fn(&E) -> u64) -> Option<MinCut> {
+        let count = self.node_count();
+        if count < 2 {
+            return None;
+        }
+
+        let mut capacity = vec![vec![0_u64; count]; count];
+        for from in self.nodes() {
+            for (to, edge) in self.edges(from).filter(|(to, _)| *to != from) {
+                capacity[from][to] += weight(edge);
+            }
+        }
+        for a in 0..count {
+            for b in (a + 1)..count {
+                let both = capacity[a][b].max(capacity[b][a]);
+                capacity[a][b] = both;
+                capacity[b][a] = both;
+            }
+        }
+
+        let mut merged = self.nodes().map(|node| vec![node]).collect::<Vec<_>>();
+        let mut active = self.nodes().collect::<Vec<_>>();
+        let mut best: Option<MinCut> = None;
+
+        while active.len() > 1 {
+            let mut added = vec![false; count];
+            let mut connectivity = vec![0_u64; count];
+            let mut prev = active[0];
+            added[prev] = true;
+            for &node in &active {
+                connectivity[node] = capacity[prev][node];
+            }
+
+            for phase in 1..active.len() {
+                let next = active
+                    .iter()
+                    .copied()
+                    .filter(|node| !added[*node])
+                    .max_by_key(|node| connectivity[*node])
+                    .unwrap();
+
+                if phase < active.len() - 1 {
+                    added[next] = true;
+                    for &node in &active {
+                        connectivity[node] += capacity[next][node];
+                    }
+                    prev = next;
+                    continue;
+                }
+
+                // Last node of the phase: its connectivity is the cut of the phase
+                if best.as_ref().is_none_or(|best| connectivity[next] < best.weight) {
+                    best = Some(MinCut {
+                        weight: connectivity[next],
+                        partition: merged[next].clone(),
+                    });
+                }
+
+                let moved = std::mem::take(&mut merged[next]);
+                merged[prev].extend(moved);
+                for node in 0..count {
+                    capacity[prev][node] += capacity[next][node];
+                    capacity[node][prev] = capacity[prev][node];
+                }
+                capacity[prev][prev] = 0;
+            }
+
+            active.retain(|node| !merged[*node].is_empty());
+        }
+
+        best.map(|mut cut| {
+            cut.partition.sort_unstable();
+            cut
+        })
+    }
+}
+
+impl<N: Hash + Eq + Clone, E: Default> Graph<N, E> {
+    pub fn add_edge<Q>(&mut self, from: &Q, to: &Q) -> (NodeId, NodeId)
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = N> + ?Sized,
+    {
+        self.add_weighted_edge(from, to, E::default())
+    }
+
+    /// Add edges in both directions
+    pub fn add_undirected_edge<Q>(&mut self, a: &Q, b: &Q) -> (NodeId, NodeId)
+    where
+        N: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = N> + ?Sized,
+    {
+        let (a, b) = self.add_edge(a, b);
+        self.add_edge_by_id(b, a, E::default());
+        (a, b)
+    }
+}
+
+impl<N: Hash + Eq + Clone> Graph<N, ()> {
+    /// Minimum cut where every edge has the weight 1
+    pub fn min_cut(&self) -> Option<MinCut> {
+        self.min_cut_by(|()| 1)
+    }
+}
+
+fn bron_kerbosch(
+    adjacency: &[HashSet<NodeId>],
+    clique: &mut Vec<NodeId>,
+    mut candidates: HashSet<NodeId>,
+    mut excluded: HashSet<NodeId>,
+    best: &mut Vec<NodeId>,
+) {
+    if candidates.is_empty() && excluded.is_empty() {
+        if clique.len() > best.len() {
+            best.clone_from(clique);
+        }
+        return;
+    }
+
+    // Can not become larger than the best clique found so far
+    if clique.len() + candidates.len() <= best.len() {
+        return;
+    }
+
+    let pivot = candidates
+        .union(&excluded)
+        .copied()
+        .max_by_key(|node| adjacency[*node].intersection(&candidates).count())
+        .unwrap();
+
+    let to_check = candidates.difference(&adjacency[pivot]).copied().collect::<Vec<_>>();
+
+    for node in to_check {
+        clique.push(node);
+        bron_kerbosch(
+            adjacency,
+            clique,
+            candidates.intersection(&adjacency[node]).copied().collect(),
+            excluded.intersection(&adjacency[node]).copied().collect(),
+            best,
+        );
+        clique.pop();
+
+        candidates.remove(&node);
+        excluded.insert(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topological_sort() {
+        let mut graph = Graph::<String>::new();
+        graph.add_edge("shirt", "tie");
+        graph.add_edge("tie", "jacket");
+        graph.add_edge("trousers", "shoes");
+        graph.add_edge("trousers", "jacket");
+
+        let sorted = graph.topological_sort().unwrap();
+        let position = |name: &str| sorted.iter().position(|id| *id == graph.id(name).unwrap()).unwrap();
+
+        assert_eq!(sorted.len(), 5);
+        assert!(position("shirt") < position("tie"));
+        assert!(position("tie") < position("jacket"));
+        assert!(position("trousers") < position("jacket"));
+        assert!(position("trousers") < position("shoes"));
+    }
+
+    #[test]
+    fn topological_sort_cycle() {
+        let mut graph = Graph::<&str>::new();
+        graph.add_edge(&"start", &"a");
+        graph.add_edge(&"a", &"b");
+        graph.add_edge(&"b", &"c");
+        graph.add_edge(&"c", &"a");
+
+        let Cycle(cycle) = graph.topological_sort().unwrap_err();
+        let names = cycle.iter().map(|id| *graph.key(*id)).collect::<Vec<_>>();
+
+        assert_eq!(names.len(), 3);
+        for (from, to) in names.iter().zip(names.iter().cycle().skip(1)) {
+            assert!(graph.has_edge(graph.id(from).unwrap(), graph.id(to).unwrap()));
+        }
+    }
+
+    #[test]
+    fn count_paths() {
+        let mut graph = Graph::<String>::new();
+        for (from, to) in [
+            ("svr", "aaa"),
+            ("svr", "bbb"),
+            ("aaa", "fft"),
+            ("fft", "ccc"),
+            ("bbb", "tty"),
+            ("tty", "ccc"),
+            ("ccc", "ddd"),
+            ("ccc", "eee"),
+            ("ddd", "hub"),
+            ("hub", "fff"),
+            ("eee", "dac"),
+            ("dac", "fff"),
+            ("fff", "ggg"),
+            ("fff", "hhh"),
+            ("ggg", "out"),
+            ("hhh", "out"),
+        ] {
+            graph.add_edge(from, to);
+        }
+
+        let id = |name: &str| graph.id(name).unwrap();
+
+        assert_eq!(graph.count_paths(id("svr"), id("out")), 8);
+        assert_eq!(graph.count_paths_through(id("svr"), id("out"), &[id("fft")]), 4);
+        assert_eq!(
+            graph.count_paths_through(id("svr"), id("out"), &[id("dac"), id("fft")]),
+            2
+        );
+    }
+
+    #[test]
+    fn max_clique() {
+        let mut graph = Graph::<String>::new();
+        for edge in ["a-b", "a-c", "b-c", "c-d", "d-e", "e-f", "d-f", "c-e", "c-f", "x-y"] {
+            let (a, b) = edge.split_once('-').unwrap();
+            graph.add_undirected_edge(a, b);
+        }
+
+        let clique = graph
+            .max_clique()
+            .into_iter()
+            .map(|id| graph.key(id).as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(clique, ["c", "d", "e", "f"]);
+    }
+
+    #[test]
+    fn min_cut() {
+        // Two triangles connected by a single edge
+        let mut graph = Graph::<String>::new();
+        for edge in ["a-b", "b-c", "c-a", "x-y", "y-z", "z-x", "c-x"] {
+            let (a, b) = edge.split_once('-').unwrap();
+            graph.add_undirected_edge(a, b);
+        }
+
+        let cut = graph.min_cut().unwrap();
+        assert_eq!(cut.weight, 1);
+
+        let mut side = cut
+            .partition
+            .iter()
+            .map(|id| graph.key(*id).as_str())
+            .collect::<Vec<_>>();
+        side.sort_unstable();
+        assert!(side == ["a", "b", "c"] || side == ["x", "y", "z"]);
+    }
+
+    #[test]
+    fn min_cut_weighted() {
+        let mut graph = Graph::<String, u64>::new();
+        for (a, b, weight) in [
+            ("1", "2", 2),
+            ("1", "5", 3),
+            ("2", "3", 3),
+            ("2", "5", 2),
+            ("2", "6", 2),
+            ("3", "4", 4),
+            ("3", "7", 2),
+            ("4", "7", 2),
+            ("4", "8", 2),
+            ("5", "6", 3),
+            ("6", "7", 1),
+            ("7", "8", 3),
+        ] {
+            graph.add_weighted_edge(a, b, weight);
+        }
+
+        assert_eq!(graph.min_cut_by(|weight| *weight).unwrap().weight, 4);
+    }
+}
@@ -0,0 +1,11 @@
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::missing_panics_doc)]
+
+pub mod automaton;
+pub mod balanced;
+pub mod bitgrid;
+pub mod graph;
+pub mod ocr;
+pub mod parse;
+pub mod random;
+pub mod render;
@@ -0,0 +1,219 @@
+/// Letters are 4x6 pixels with one empty column in between
+const FONT_4X6: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+/// Letters are 6x10 pixels with two empty columns in between
+const FONT_6X10: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+/// Pixels of an ascii art, `#`, `X` and `█` are lit, everything else is dark
+pub fn parse_art(art: &str) -> Vec<Vec<bool>> {
+    art.lines()
+        .map(|line| line.chars().map(|ch| matches!(ch, '#' | 'X' | '█')).collect())
+        .collect()
+}
+
+/// Ascii art using `#` and `.`, one line per row
+pub fn to_art<R: AsRef<[bool]>>(pixels: &[R]) -> String {
+    pixels
+        .iter()
+        .map(|row| {
+            row.as_ref()
+                .iter()
+                .map(|&lit| if lit { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Rows of the glyph with empty rows and columns around it removed, rows may have different lengths
+fn trimmed<R: AsRef<[bool]>>(pixels: &[R]) -> Vec<Vec<bool>> {
+    let lit_rows = || {
+        pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.as_ref().contains(&true))
+    };
+    let (Some((top, _)), Some((bottom, _))) = (lit_rows().next(), lit_rows().last()) else {
+        return vec![];
+    };
+
+    let lit_columns = || {
+        pixels
+            .iter()
+            .flat_map(|row| row.as_ref().iter().enumerate().filter(|(_, &lit)| lit))
+    };
+    let left = lit_columns().map(|(x, _)| x).min().unwrap_or(0);
+    let right = lit_columns().map(|(x, _)| x).max().unwrap_or(0);
+
+    pixels[top..=bottom]
+        .iter()
+        .map(|row| (left..=right).map(|x| row.as_ref().get(x) == Some(&true)).collect())
+        .collect()
+}
+
+/// Letters drawn with one of the puzzle fonts, `None` if any glyph is unknown.
+///
+/// Glyphs are split at empty columns, so the art may be shifted or padded in any direction.
+pub fn recognize<R: AsRef<[bool]>>(pixels: &[R]) -> Option<String> {
+    let pixels = trimmed(pixels);
+    let font: &[(char, &str)] = match pixels.len() {
+        6 => &FONT_4X6,
+        10 => &FONT_6X10,
+        _ => return None,
+    };
+    let font = font
+        .iter()
+        .map(|(letter, art)| (trimmed(&parse_art(art)), *letter))
+        .collect::<Vec<_>>();
+
+    let width = pixels[0].len();
+    let empty_column = |x: usize| pixels.iter().all(|row| !row[x]);
+
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < width {
+        if empty_column(x) {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        while x < width && !empty_column(x) {
+            x += 1;
+        }
+
+        let glyph = trimmed(&pixels.iter().map(|row| &row[start..x]).collect::<Vec<_>>());
+        let (_, letter) = font.iter().find(|(known, _)| *known == glyph)?;
+        letters.push(*letter);
+    }
+
+    Some(letters)
+}
+
+/// The recognized letters, or the art itself (starting with a newline) so it can still be read by eye
+pub fn recognize_or_art<R: AsRef<[bool]>>(pixels: &[R]) -> String {
+    recognize(pixels).unwrap_or_else(|| format!("\n{}", to_art(pixels)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_font() {
+        let art = "
+ ##  #    ###  #### #  # #### #  # #  #
+#  # #    #  # #    # #  #    # #  #  #
+#  # #    #  # ###  ##   ###  ##   #  #
+#### #    ###  #    # #  #    # #  #  #
+#  # #    # #  #    # #  #    # #  #  #
+#  # #### #  # #### #  # #    #  #  ## ";
+
+        assert_eq!(recognize(&parse_art(art)).as_deref(), Some("ALREKFKU"));
+    }
+
+    #[test]
+    fn large_font() {
+        let letters = ['H', 'X', 'J'].map(|letter| FONT_6X10.iter().find(|(ch, _)| *ch == letter).unwrap().1);
+        let rows = (0..10)
+            .map(|y| {
+                letters
+                    .iter()
+                    .map(|art| art.lines().nth(y).unwrap())
+                    .collect::<Vec<_>>()
+                    .join("..")
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(recognize(&parse_art(&rows.join("\n"))).as_deref(), Some("HXJ"));
+    }
+
+    #[test]
+    fn unknown_glyph_falls_back_to_art() {
+        let pixels = parse_art("#####\n#...#\n#...#\n#...#\n#...#\n#####");
+
+        assert_eq!(recognize(&pixels), None);
+        assert_eq!(recognize_or_art(&pixels), "\n#####\n#...#\n#...#\n#...#\n#...#\n#####");
+        assert_eq!(recognize::<Vec<bool>>(&[]), None);
+    }
+}
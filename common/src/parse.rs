@@ -0,0 +1,263 @@
+use std::{fmt, str::FromStr};
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{char, digit1, line_ending, multispace0, one_of, satisfy, space0, space1},
+    combinator::{all_consuming, map_opt, map_parser, map_res, opt, recognize, verify},
+    error::{Error, ErrorKind, ParseError},
+    multi::{many1, many1_count, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    Err, IResult, Parser,
+};
+
+/// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
+/// trailing whitespace, returning the output of `inner`.
+/// https://github.com/Geal/nom/blob/main/doc/nom_recipes.md
+pub fn ws<'a, F, O, E>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+where
+    F: Fn(&'a str) -> IResult<&'a str, O, E> + 'a,
+    E: ParseError<&'a str>,
+{
+    delimited(multispace0, inner, multispace0)
+}
+
+/// Integer with an optional `+`/`-` sign, e.g. `signed::<i64>`
+pub fn signed<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse)(input)
+}
+
+/// Integer without a sign, e.g. `unsigned::<u8>`
+pub fn unsigned<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// Numbers separated by a comma and optional spaces, e.g. `0,3,5,4,3,0`
+pub fn comma_separated<T: FromStr>(input: &str) -> IResult<&str, Vec<T>> {
+    separated_list1(tuple((space0, char(','), space0)), signed)(input)
+}
+
+/// Numbers separated by spaces on a single line, e.g. `79 14 55 13`
+pub fn space_separated<T: FromStr>(input: &str) -> IResult<&str, Vec<T>> {
+    separated_list1(space1, signed)(input)
+}
+
+/// Lines up to (not including) the next blank line
+fn block(input: &str) -> IResult<&str, &str> {
+    recognize(separated_list1(
+        line_ending,
+        verify(
+            recognize(many1_count(satisfy(|ch| ch != '\r' && ch != '\n'))),
+            |line: &str| !line.trim().is_empty(),
+        ),
+    ))(input)
+}
+
+/// Apply `inner` to every block of lines, blocks are separated by blank lines.
+///
+/// `inner` has to consume its whole block.
+pub fn blocks<'a, O, F>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    separated_list1(
+        pair(line_ending, many1_count(pair(space0, line_ending))),
+        map_parser(block, all_consuming(inner)),
+    )
+}
+
+/// `key: value` with optional spaces around the colon
+pub fn key_value<'a, K, V, FK, FV>(key: FK, value: FV) -> impl FnMut(&'a str) -> IResult<&'a str, (K, V)>
+where
+    FK: Parser<&'a str, K, Error<&'a str>>,
+    FV: Parser<&'a str, V, Error<&'a str>>,
+{
+    separated_pair(key, tuple((space0, char(':'), space0)), value)
+}
+
+/// Value behind a fixed label, e.g. `labeled("Register A", signed::<i64>)` for `Register A: 729`
+pub fn labeled<'a, O, F>(label: &'a str, value: F) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    preceded(tuple((tag(label), space0, char(':'), space0)), value)
+}
+
+/// Rectangular grid of characters, `cell` maps every character to a value or rejects it
+pub fn grid<'a, T, F>(cell: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<Vec<T>>>
+where
+    F: Fn(char) -> Option<T> + Copy,
+{
+    verify(
+        separated_list1(
+            line_ending,
+            many1(map_opt(satisfy(|ch| ch != '\r' && ch != '\n'), cell)),
+        ),
+        |rows: &Vec<Vec<T>>| rows.iter().all(|row| row.len() == rows[0].len()),
+    )
+}
+
+pub fn char_grid(input: &str) -> IResult<&str, Vec<Vec<char>>> {
+    grid(Some)(input)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAllError {
+    /// The parser itself failed at the given position
+    Failed {
+        kind: ErrorKind,
+        line: usize,
+        column: usize,
+        context: String,
+    },
+    /// The parser succeeded but did not consume the whole input
+    Leftover {
+        line: usize,
+        column: usize,
+        context: String,
+    },
+}
+
+impl fmt::Display for ParseAllError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAllError::Failed {
+                kind,
+                line,
+                column,
+                context,
+            } => write!(f, "parser failed ({kind:?}) at {line}:{column}: '{context}'"),
+            ParseAllError::Leftover { line, column, context } => {
+                write!(f, "input not fully consumed at {line}:{column}: '{context}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseAllError {}
+
+/// Run `parser` on the whole `input`, only trailing whitespace may be left over
+pub fn parse_all<'a, O, F>(parser: F, input: &'a str) -> Result<O, ParseAllError>
+where
+    F: Parser<&'a str, O, Error<&'a str>>,
+{
+    let position = |rest: &str| {
+        let consumed = &input[..input.len() - rest.len()];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed.len() - consumed.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+        let context = rest.lines().next().unwrap_or_default().to_string();
+
+        (line, column, context)
+    };
+
+    match terminated(parser, multispace0)(input) {
+        Ok(("", output)) => Ok(output),
+        Ok((rest, _)) => {
+            let (line, column, context) = position(rest);
+            Err(ParseAllError::Leftover { line, column, context })
+        }
+        Err(Err::Error(error) | Err::Failure(error)) => {
+            let (line, column, context) = position(error.input);
+            Err(ParseAllError::Failed {
+                kind: error.code,
+                line,
+                column,
+                context,
+            })
+        }
+        Err(Err::Incomplete(_)) => {
+            let (line, column, context) = position("");
+            Err(ParseAllError::Failed {
+                kind: ErrorKind::Complete,
+                line,
+                column,
+                context,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::character::complete::{alpha1, multispace1};
+
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(signed::<i8>("-128"), Ok(("", -128)));
+        assert_eq!(signed::<u64>("+42 rest"), Ok((" rest", 42)));
+        assert!(signed::<u8>("256").is_err());
+        assert!(unsigned::<u32>("-1").is_err());
+
+        assert_eq!(comma_separated::<i32>("0,3, -5 ,4"), Ok(("", vec![0, 3, -5, 4])));
+        assert_eq!(space_separated::<u16>("79 14  55\n13"), Ok(("\n13", vec![79, 14, 55])));
+    }
+
+    #[test]
+    fn registers() {
+        let input = "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1,5,4,3,0\n";
+
+        let registers = tuple((
+            terminated(labeled("Register A", signed::<i64>), line_ending),
+            terminated(labeled("Register B", signed::<i64>), line_ending),
+            labeled("Register C", signed::<i64>),
+        ));
+        let program = labeled("Program", comma_separated::<u8>);
+
+        let (registers, program) = parse_all(separated_pair(registers, multispace1, program), input).unwrap();
+
+        assert_eq!(registers, (729, 0, 0));
+        assert_eq!(program, [0, 1, 5, 4, 3, 0]);
+    }
+
+    #[test]
+    fn blocks_of_lines() {
+        let input = "1000\r\n2000\r\n\r\n4000\n\n\n5000\n6000";
+
+        let sums = parse_all(
+            blocks(separated_list1(line_ending, unsigned::<u32>).map(|nbs| nbs.iter().sum::<u32>())),
+            input,
+        );
+
+        assert_eq!(sums, Ok(vec![3000, 4000, 11000]));
+    }
+
+    #[test]
+    fn key_values_and_grids() {
+        assert_eq!(
+            key_value(alpha1, space_separated::<u8>)("Time:      7  15   30"),
+            Ok(("", ("Time", vec![7, 15, 30])))
+        );
+
+        let walls = grid(|ch| match ch {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        });
+        assert_eq!(
+            parse_all(walls, "#.\n.#\n"),
+            Ok(vec![vec![true, false], vec![false, true]])
+        );
+
+        assert!(char_grid("abc\nde").is_err());
+    }
+
+    #[test]
+    fn leftover_is_reported() {
+        let error = parse_all(comma_separated::<u8>, "1,2,3\n4,5").unwrap_err();
+
+        assert_eq!(
+            error,
+            ParseAllError::Leftover {
+                line: 2,
+                column: 1,
+                context: "4,5".to_string()
+            }
+        );
+
+        assert!(matches!(
+            parse_all(unsigned::<u8>, "x"),
+            Err(ParseAllError::Failed { line: 1, column: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,34 @@
+/// Xorshift generator for property tests, the same seed always gives the same numbers
+#[derive(Debug, Clone)]
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift only produces zeros from a zero seed");
+        Self(seed)
+    }
+
+    /// Next number in `0..below`
+    pub fn next(&mut self, below: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % below
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let mut a = Xorshift::new(42);
+        let mut b = Xorshift::new(42);
+        let numbers = (0..100).map(|_| a.next(6)).collect::<Vec<_>>();
+
+        assert_eq!(numbers, (0..100).map(|_| b.next(6)).collect::<Vec<_>>());
+        assert!(numbers.iter().all(|nb| *nb < 6));
+        assert!((0..6).all(|nb| numbers.contains(&nb)));
+    }
+}
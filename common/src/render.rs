@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::Hash,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// Maps cell values (e.g. the chars of a puzzle map) to colors
+#[derive(Debug, Clone)]
+pub struct Palette<K> {
+    colors: HashMap<K, Rgb>,
+    fallback: Rgb,
+}
+
+impl<K: Hash + Eq> Palette<K> {
+    /// `fallback` is used for all values without a color
+    pub fn new(fallback: Rgb) -> Self {
+        Self {
+            colors: HashMap::new(),
+            fallback,
+        }
+    }
+
+    pub fn with(mut self, key: K, color: Rgb) -> Self {
+        self.colors.insert(key, color);
+        self
+    }
+
+    pub fn color(&self, key: &K) -> Rgb {
+        self.colors.get(key).copied().unwrap_or(self.fallback)
+    }
+}
+
+/// RGB image, the origin is the top left corner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, pixel: impl Fn(usize, usize) -> Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| pixel(x, y))
+                .collect(),
+        }
+    }
+
+    /// One pixel per cell, e.g. `Frame::from_grid(&map, |ch| palette.color(ch))`
+    pub fn from_grid<T, R: AsRef<[T]>>(rows: &[R], color: impl Fn(&T) -> Rgb) -> Self {
+        let width = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+
+        let mut frame = Self::new(width, rows.len(), BLACK);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.as_ref().iter().enumerate() {
+                frame.set(x, y, color(cell));
+            }
+        }
+        frame
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Pixels outside of the frame are ignored, so callers do not have to clip
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Every pixel becomes a `factor` x `factor` square, puzzle grids are tiny otherwise
+    pub fn scaled(&self, factor: usize) -> Frame {
+        Frame::from_fn(self.width * factor, self.height * factor, |x, y| {
+            self.get(x / factor, y / factor)
+        })
+    }
+
+    fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+
+    /// Binary PPM (P6), can be opened by most image viewers without any encoder
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        data.extend(self.rgb_bytes());
+        data
+    }
+
+    pub fn write_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&self.to_ppm())?;
+        file.flush()
+    }
+
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&self.rgb_bytes()).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+}
+
+/// Collects frames of a running simulation
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    frames: Vec<Frame>,
+    every: usize,
+    calls: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            frames: vec![],
+            every: 1,
+            calls: 0,
+        }
+    }
+
+    /// Only keep every `nth` recorded frame, long simulations get huge otherwise
+    pub fn every(mut self, nth: usize) -> Self {
+        self.every = nth.max(1);
+        self
+    }
+
+    pub fn record(&mut self, frame: Frame) {
+        if self.calls.is_multiple_of(self.every) {
+            self.frames.push(frame);
+        }
+        self.calls += 1;
+    }
+
+    /// Hook for a simulation which calls it with its state after every step
+    pub fn hook<'a, S: ?Sized>(&'a mut self, render: impl Fn(&S) -> Frame + 'a) -> impl FnMut(&S) + 'a {
+        move |state| {
+            // Skip rendering frames that are not kept anyway
+            if self.calls.is_multiple_of(self.every) {
+                self.frames.push(render(state));
+            }
+            self.calls += 1;
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Animated GIF looping forever, `delay_ms` between frames (GIF only supports steps of 10ms)
+    pub fn write_gif(&self, path: impl AsRef<Path>, delay_ms: u16) -> io::Result<()> {
+        let Some(first) = self.frames.first() else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no frames recorded"));
+        };
+
+        if self
+            .frames
+            .iter()
+            .any(|frame| frame.width != first.width || frame.height != first.height)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "all frames must have the same size",
+            ));
+        }
+
+        let (width, height) = (
+            u16::try_from(first.width).map_err(io::Error::other)?,
+            u16::try_from(first.height).map_err(io::Error::other)?,
+        );
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, width, height, &[]).map_err(io::Error::other)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+
+        for frame in &self.frames {
+            let mut gif_frame = gif::Frame::from_rgb_speed(width, height, &frame.rgb_bytes(), 10);
+            gif_frame.delay = delay_ms / 10;
+            encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+        }
+
+        Ok(())
+    }
+
+    /// `frame_00000.png`, `frame_00001.png`, ... in `dir`, which is created if missing
+    pub fn write_png_sequence(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+
+        for (idx, frame) in self.frames.iter().enumerate() {
+            frame.write_png(dir.as_ref().join(format!("frame_{idx:05}.png")))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-common-render-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn grid_with_palette() {
+        let map = ["#.o", "..#"].map(|row| row.chars().collect::<Vec<_>>());
+        let palette = Palette::new(BLACK).with('#', WHITE).with('o', [194, 178, 128]);
+
+        let frame = Frame::from_grid(&map, |ch| palette.color(ch)).scaled(2);
+
+        assert_eq!((frame.width(), frame.height()), (6, 4));
+        assert_eq!(frame.get(1, 1), WHITE);
+        assert_eq!(frame.get(2, 0), BLACK);
+        assert_eq!(frame.get(5, 1), [194, 178, 128]);
+
+        let ppm = frame.to_ppm();
+        assert!(ppm.starts_with(b"P6\n6 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 6 * 4 * 3);
+    }
+
+    #[test]
+    fn png_sequence() {
+        let dir = temp_dir("png");
+
+        let mut recorder = Recorder::new().every(2);
+        {
+            let mut hook = recorder.hook(|step: &usize| Frame::from_fn(4, 3, |x, _| [(x * step) as u8, 0, 0]));
+            for step in 0..5 {
+                hook(&step);
+            }
+        }
+
+        assert_eq!(recorder.frames().len(), 3);
+        recorder.write_png_sequence(&dir).unwrap();
+
+        let decoder = png::Decoder::new(File::open(dir.join("frame_00002.png")).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+
+        assert_eq!((info.width, info.height), (4, 3));
+        assert_eq!(buffer[..6], [0, 0, 0, 4, 0, 0]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn animated_gif() {
+        let dir = temp_dir("gif");
+        let path = dir.join("animation.gif");
+
+        let mut recorder = Recorder::new();
+        for step in 0..4 {
+            let mut frame = Frame::new(5, 5, BLACK);
+            frame.set(step, step, WHITE);
+            recorder.record(frame);
+        }
+        recorder.write_gif(&path, 100).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames += 1;
+        }
+        assert_eq!(frames, 4);
+
+        recorder.record(Frame::new(2, 2, BLACK));
+        assert!(recorder.write_gif(&path, 100).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}